@@ -7,6 +7,16 @@ pub trait AStarSearcher: Sized {
   type Successors: IntoIterator<Item = Self::Node> + std::iter::FromIterator<Self::Node>;
 
   fn search(&mut self, initial: Self::Node) -> Option<(u64, Self::Node)> {
+    self.search_observed(initial, |_| {})
+  }
+
+  /// Like `search`, but calls `observer` every time a node is expanded, once
+  /// its successors have joined the frontier.
+  fn search_observed(
+    &mut self,
+    initial: Self::Node,
+    mut observer: impl FnMut(Expansion<Self::Node>),
+  ) -> Option<(u64, Self::Node)> {
     let mut heap: BinaryHeap<SearchNode<Self::Node>> = BinaryHeap::new();
     heap.push(SearchNode {
       steps_so_far: 0,
@@ -25,6 +35,11 @@ pub trait AStarSearcher: Sized {
           heuristic,
         });
       }
+      observer(Expansion {
+        node: &state.node,
+        steps_so_far: state.steps_so_far,
+        heap: &heap,
+      });
     }
     return None;
   }
@@ -40,6 +55,25 @@ pub trait AStarSearcher: Sized {
   fn successors(&mut self, node: &Self::Node) -> Self::Successors;
 }
 
+/// A node being expanded by `AStarSearcher::search_observed`.
+pub struct Expansion<'a, T>
+where
+  T: Sized + Eq,
+{
+  pub node: &'a T,
+  pub steps_so_far: u64,
+  heap: &'a BinaryHeap<SearchNode<T>>,
+}
+impl<'a, T> Expansion<'a, T>
+where
+  T: Sized + Eq,
+{
+  /// The nodes waiting to be expanded, in no particular order.
+  pub fn frontier(&self) -> impl Iterator<Item = &'a T> {
+    self.heap.iter().map(|n| &n.node)
+  }
+}
+
 pub struct CachingSearcher<Searcher>
 where
   Searcher: AStarSearcher,
@@ -1,33 +1,50 @@
 #![allow(dead_code)]
 
-use std::{error::Error, str::FromStr};
+use std::{
+  collections::{HashMap, VecDeque},
+  error::Error,
+  str::FromStr,
+};
 
 use regex::Regex;
+use smallvec::SmallVec;
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
-enum Register {
-  A,
-  B,
-  C,
-  D,
+/// A register, named by a lowercase letter starting from `a`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Register(u8);
+impl Register {
+  pub const A: Register = Register(0);
+  pub const B: Register = Register(1);
+  pub const C: Register = Register(2);
+  pub const D: Register = Register(3);
+
+  pub fn index(self) -> usize {
+    self.0 as usize
+  }
+
+  /// The first `count` registers, starting from `a`.
+  pub fn all(count: usize) -> impl Iterator<Item = Register> {
+    (0..count as u8).map(Register)
+  }
+}
+impl std::fmt::Display for Register {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", (b'a' + self.0) as char)
+  }
 }
 impl FromStr for Register {
   type Err = Box<dyn Error>;
 
   fn from_str(line: &str) -> Result<Self, Self::Err> {
-    let result = match line {
-      "a" => Register::A,
-      "b" => Register::B,
-      "c" => Register::C,
-      "d" => Register::D,
-      v => return Err(format!("Unknown register: {}", v).into()),
-    };
-    Ok(result)
+    match line.as_bytes() {
+      [c @ b'a'..=b'z'] => Ok(Register(c - b'a')),
+      _ => Err(format!("Unknown register: {}", line).into()),
+    }
   }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
-enum RegisterOrInteger {
+pub enum RegisterOrInteger {
   I(i64),
   R(Register),
 }
@@ -35,16 +52,32 @@ impl FromStr for RegisterOrInteger {
   type Err = Box<dyn Error>;
 
   fn from_str(line: &str) -> Result<Self, Self::Err> {
-    let result = match line {
-      "a" | "b" | "c" | "d" => RegisterOrInteger::R(line.parse()?),
-      v => RegisterOrInteger::I(v.parse()?),
+    let result = match line.as_bytes() {
+      [b'a'..=b'z'] => RegisterOrInteger::R(line.parse()?),
+      _ => RegisterOrInteger::I(line.parse()?),
     };
     Ok(result)
   }
 }
+impl RegisterOrInteger {
+  fn register(self) -> Option<Register> {
+    match self {
+      RegisterOrInteger::I(_) => None,
+      RegisterOrInteger::R(r) => Some(r),
+    }
+  }
+}
+impl std::fmt::Display for RegisterOrInteger {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      RegisterOrInteger::I(i) => write!(f, "{}", i),
+      RegisterOrInteger::R(r) => write!(f, "{}", r),
+    }
+  }
+}
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
-enum Instruction {
+pub enum Instruction {
   Copy {
     from: RegisterOrInteger,
     to: Register,
@@ -61,11 +94,13 @@ impl FromStr for Instruction {
 
   fn from_str(line: &str) -> Result<Self, Self::Err> {
     lazy_static! {
-      static ref CPY_ARGS_RE: Regex = Regex::new(r"(a|b|c|d|-?\d+) (a|b|c|d)").unwrap();
-      static ref JNZ_ARGS_RE: Regex = Regex::new(r"(a|b|c|d|-?\d+) (a|b|c|d|-?\d+)").unwrap();
+      static ref CPY_ARGS_RE: Regex = Regex::new(r"([a-z]|-?\d+) ([a-z])").unwrap();
+      static ref JNZ_ARGS_RE: Regex = Regex::new(r"([a-z]|-?\d+) ([a-z]|-?\d+)").unwrap();
     }
-    let instr = &line[0..3];
-    let args = &line[4..];
+    let (instr, args) = match (line.get(0..3), line.get(4..)) {
+      (Some(instr), Some(args)) => (instr, args),
+      _ => return Err(format!("Couldn't parse instruction: {}", line).into()),
+    };
     let result = match instr {
       "cpy" => {
         let captures = CPY_ARGS_RE
@@ -89,90 +124,427 @@ impl FromStr for Instruction {
           offset: captures[2].parse()?,
         }
       }
-      s => {
-        panic!("Unknown instruction: {}", s)
-      }
+      s => return Err(format!("Unknown instruction: {}", s).into()),
     };
     Ok(result)
   }
 }
+impl std::fmt::Display for Instruction {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Instruction::Copy { from, to } => write!(f, "cpy {} {}", from, to),
+      Instruction::Increment(r) => write!(f, "inc {}", r),
+      Instruction::Decrement(r) => write!(f, "dec {}", r),
+      Instruction::JumpNotZero { test, offset } => write!(f, "jnz {} {}", test, offset),
+    }
+  }
+}
 
-struct Computer {
-  instructions: Vec<Instruction>,
-  program_counter: usize,
-  a: i64,
-  b: i64,
-  c: i64,
-  d: i64,
+/// Formats a program back into assembunny source, one instruction per line.
+pub fn format_program(instructions: &[Instruction]) -> String {
+  instructions.iter().map(|i| format!("{}\n", i)).collect()
 }
-impl Computer {
-  fn new(program: &str) -> Self {
+
+/// Prints the assembunny program at `path` in its canonical format.
+pub fn print_formatted_file(path: &str) -> Result<(), Box<dyn Error>> {
+  let program = std::fs::read_to_string(path)?;
+  let computer = Computer::with_config(&program, Config::default())?;
+  print!("{}", format_program(&computer.instructions));
+  Ok(())
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Overflow {
+  Wrap,
+  Saturate,
+  // Halts the computer instead of writing a value that doesn't fit.
+  Trap,
+}
+
+/// The shape of the machine a program runs on.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Config {
+  // Programs may use registers `a` up to the `registers`th letter.
+  pub registers: usize,
+  // Registers hold signed integers of this many bits, from 1 to 64.
+  pub bits: u32,
+  pub overflow: Overflow,
+}
+impl std::fmt::Display for Overflow {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.write_str(match self {
+      Overflow::Wrap => "wrap",
+      Overflow::Saturate => "saturate",
+      Overflow::Trap => "trap",
+    })
+  }
+}
+impl FromStr for Overflow {
+  type Err = Box<dyn Error>;
+
+  fn from_str(line: &str) -> Result<Self, Self::Err> {
+    let result = match line {
+      "wrap" => Overflow::Wrap,
+      "saturate" => Overflow::Saturate,
+      "trap" => Overflow::Trap,
+      v => return Err(format!("Unknown overflow behavior: {}", v).into()),
+    };
+    Ok(result)
+  }
+}
+
+impl Default for Config {
+  fn default() -> Self {
     Self {
-      instructions: program.trim().lines().map(|l| l.parse().unwrap()).collect(),
+      registers: 4,
+      bits: 64,
+      overflow: Overflow::Wrap,
+    }
+  }
+}
+impl Config {
+  pub fn registers(&self) -> impl Iterator<Item = Register> {
+    Register::all(self.registers)
+  }
+
+  fn min(&self) -> i128 {
+    -(1 << (self.bits - 1))
+  }
+
+  fn max(&self) -> i128 {
+    (1 << (self.bits - 1)) - 1
+  }
+
+  /// Fits `value` into a register, or None if it overflows and we're
+  /// trapping.
+  fn fit(&self, value: i128) -> Option<i64> {
+    if value >= self.min() && value <= self.max() {
+      return Some(value as i64);
+    }
+    match self.overflow {
+      Overflow::Wrap => Some(((value - self.min()).rem_euclid(1 << self.bits) + self.min()) as i64),
+      Overflow::Saturate => Some(value.max(self.min()).min(self.max()) as i64),
+      Overflow::Trap => None,
+    }
+  }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum HaltReason {
+  // Ran past the last instruction.
+  EndOfProgram,
+  JumpOutOfBounds { pc: usize, target: i64 },
+  Overflow { pc: usize },
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Step {
+  Running,
+  Halted { reason: HaltReason },
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RunResult {
+  Halted { reason: HaltReason },
+  // Ran out of steps before halting or revisiting a state.
+  StepLimit,
+  // The computer returned to an exact earlier state, so will repeat the
+  // same `period` steps forever.
+  Looping { period: u64 },
+}
+
+pub struct Computer {
+  pub config: Config,
+  pub instructions: Vec<Instruction>,
+  pub program_counter: usize,
+  pub registers: Vec<i64>,
+  halted: Option<HaltReason>,
+}
+impl Computer {
+  pub fn new(program: &str) -> Self {
+    Self::with_config(program, Config::default()).unwrap()
+  }
+
+  pub fn with_config(program: &str, config: Config) -> Result<Self, Box<dyn Error>> {
+    if config.bits == 0 || config.bits > 64 {
+      return Err(format!("Registers can't be {} bits wide", config.bits).into());
+    }
+    if config.registers > 26 {
+      return Err("Only registers a-z are supported".into());
+    }
+    let instructions = program
+      .trim()
+      .lines()
+      .map(|l| l.parse())
+      .collect::<Result<Vec<Instruction>, _>>()?;
+    for (idx, instruction) in instructions.iter().enumerate() {
+      let registers = match instruction {
+        Instruction::Copy { from, to } => [Some(*to), from.register()],
+        Instruction::Increment(r) | Instruction::Decrement(r) => [Some(*r), None],
+        Instruction::JumpNotZero { test, offset } => [test.register(), offset.register()],
+      };
+      for register in registers.iter().flatten() {
+        if register.index() >= config.registers {
+          return Err(
+            format!(
+              "Line {} uses register {}, but there are only {}",
+              idx + 1,
+              register,
+              config.registers
+            )
+            .into(),
+          );
+        }
+      }
+    }
+    Ok(Self {
+      config,
+      instructions,
       program_counter: 0,
-      a: 0,
-      b: 0,
-      c: 0,
-      d: 0,
+      registers: vec![0; config.registers],
+      halted: None,
+    })
+  }
+
+  pub fn step(&mut self) -> Step {
+    if let Some(reason) = self.halted {
+      return Step::Halted { reason };
+    }
+    match self.execute() {
+      Ok(()) => Step::Running,
+      Err(reason) => {
+        self.halted = Some(reason);
+        Step::Halted { reason }
+      }
     }
   }
 
-  fn step(&mut self) -> bool {
-    let instruction = match self.instructions.get(self.program_counter) {
+  fn execute(&mut self) -> Result<(), HaltReason> {
+    let pc = self.program_counter;
+    let instruction = match self.instructions.get(pc) {
       Some(i) => *i,
-      None => return false,
+      None => return Err(HaltReason::EndOfProgram),
     };
+    let fit = |value: i128| self.config.fit(value).ok_or(HaltReason::Overflow { pc });
     match instruction {
       Instruction::Copy { from, to } => {
-        self.write_register(to, self.read_register_or_value(from));
+        let value = fit(self.read_register_or_value(from) as i128)?;
+        self.write_register(to, value);
       }
       Instruction::Increment(r) => {
-        self.write_register(r, self.read_register(r) + 1);
+        let value = fit(self.read_register(r) as i128 + 1)?;
+        self.write_register(r, value);
       }
       Instruction::Decrement(r) => {
-        self.write_register(r, self.read_register(r) - 1);
+        let value = fit(self.read_register(r) as i128 - 1)?;
+        self.write_register(r, value);
       }
       Instruction::JumpNotZero { test, offset } => {
         if self.read_register_or_value(test) != 0 {
-          let pc = (self.program_counter as i64)
-            .overflowing_add(self.read_register_or_value(offset))
-            .0;
-          self.program_counter = pc as usize;
-          return true;
+          let target = (pc as i64).saturating_add(self.read_register_or_value(offset));
+          if target < 0 || target > self.instructions.len() as i64 {
+            return Err(HaltReason::JumpOutOfBounds { pc, target });
+          }
+          self.program_counter = target as usize;
+          return Ok(());
         }
       }
     }
     self.program_counter += 1;
-    return true;
+    Ok(())
   }
 
-  fn run_to_completion(&mut self) {
-    while self.step() {}
+  pub fn run_to_completion(&mut self) -> HaltReason {
+    loop {
+      if let Step::Halted { reason } = self.step() {
+        return reason;
+      }
+    }
   }
 
-  fn read_register_or_value(&self, v: RegisterOrInteger) -> i64 {
+  /// Runs for at most `max_steps`, stopping early if the program halts or
+  /// is stuck in a loop.
+  ///
+  /// Every (pc, registers) state is remembered along with the step it was
+  /// seen at, so a loop is reported as soon as a state repeats exactly. That
+  /// costs memory in proportion to the number of steps taken, so use
+  /// `run_to_completion` for programs known to halt.
+  pub fn run_with_budget(&mut self, max_steps: u64) -> RunResult {
+    let mut seen: HashMap<(usize, SmallVec<[i64; 4]>), u64> = HashMap::new();
+    seen.insert(self.state(), 0);
+    for step in 1..=max_steps {
+      if let Step::Halted { reason } = self.step() {
+        return RunResult::Halted { reason };
+      }
+      if let Some(first) = seen.insert(self.state(), step) {
+        return RunResult::Looping {
+          period: step - first,
+        };
+      }
+    }
+    RunResult::StepLimit
+  }
+
+  fn state(&self) -> (usize, SmallVec<[i64; 4]>) {
+    (self.program_counter, SmallVec::from_slice(&self.registers))
+  }
+
+  pub fn snapshot(&self) -> Snapshot {
+    Snapshot {
+      config: self.config,
+      instructions: self.instructions.clone(),
+      program_counter: self.program_counter,
+      registers: self.registers.clone(),
+    }
+  }
+
+  pub fn from_snapshot(snapshot: Snapshot) -> Self {
+    Self {
+      config: snapshot.config,
+      instructions: snapshot.instructions,
+      program_counter: snapshot.program_counter,
+      registers: snapshot.registers,
+      halted: None,
+    }
+  }
+
+  pub fn read_register_or_value(&self, v: RegisterOrInteger) -> i64 {
     match v {
       RegisterOrInteger::I(i) => i,
       RegisterOrInteger::R(r) => self.read_register(r),
     }
   }
 
-  fn read_register(&self, register: Register) -> i64 {
-    match register {
-      Register::A => self.a,
-      Register::B => self.b,
-      Register::C => self.c,
-      Register::D => self.d,
+  pub fn read_register(&self, register: Register) -> i64 {
+    self.registers[register.index()]
+  }
+
+  pub fn write_register(&mut self, register: Register, value: i64) {
+    self.registers[register.index()] = value;
+  }
+}
+
+/// A copy of a computer's complete state, which can be cloned to try out
+/// different register values, or saved to disk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+  pub config: Config,
+  pub instructions: Vec<Instruction>,
+  pub program_counter: usize,
+  pub registers: Vec<i64>,
+}
+impl Snapshot {
+  pub fn save(&self, path: &str) -> std::io::Result<()> {
+    std::fs::write(path, self.to_string())
+  }
+
+  pub fn load(path: &str) -> Result<Self, Box<dyn Error>> {
+    std::fs::read_to_string(path)?.parse()
+  }
+}
+impl std::fmt::Display for Snapshot {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    writeln!(
+      f,
+      "config {} {} {}",
+      self.config.registers, self.config.bits, self.config.overflow
+    )?;
+    writeln!(f, "pc {}", self.program_counter)?;
+    f.write_str("registers")?;
+    for value in self.registers.iter() {
+      write!(f, " {}", value)?;
     }
+    writeln!(f)?;
+    writeln!(f, "program")?;
+    f.write_str(&format_program(&self.instructions))
   }
+}
+impl FromStr for Snapshot {
+  type Err = Box<dyn Error>;
 
-  fn write_register(&mut self, register: Register, value: i64) {
-    match register {
-      Register::A => self.a = value,
-      Register::B => self.b = value,
-      Register::C => self.c = value,
-      Register::D => self.d = value,
+  fn from_str(snapshot: &str) -> Result<Self, Self::Err> {
+    let mut lines = snapshot.lines();
+    let mut header = |name: &str| -> Result<Vec<&str>, Box<dyn Error>> {
+      let line = lines.next().ok_or("Snapshot is truncated")?;
+      let mut words = line.split_whitespace();
+      if words.next() != Some(name) {
+        return Err(format!("Expected {} in snapshot, found: {}", name, line).into());
+      }
+      Ok(words.collect())
+    };
+    let config = match header("config")?[..] {
+      [registers, bits, overflow] => Config {
+        registers: registers.parse()?,
+        bits: bits.parse()?,
+        overflow: overflow.parse()?,
+      },
+      _ => return Err("Couldn't parse snapshot config".into()),
+    };
+    let program_counter = match header("pc")?[..] {
+      [pc] => pc.parse()?,
+      _ => return Err("Couldn't parse snapshot pc".into()),
+    };
+    let registers = header("registers")?
+      .into_iter()
+      .map(|v| v.parse())
+      .collect::<Result<Vec<i64>, _>>()?;
+    header("program")?;
+    let program: Vec<&str> = lines.collect();
+    let computer = Computer::with_config(&program.join("\n"), config)?;
+    if registers.len() != config.registers {
+      return Err("Snapshot has the wrong number of registers".into());
     }
+    Ok(Snapshot {
+      config,
+      instructions: computer.instructions,
+      program_counter,
+      registers,
+    })
+  }
+}
+
+/// The most recent states of a computer, for stepping backwards. Only the
+/// program counter and registers are kept, as nothing modifies the program.
+pub struct History {
+  capacity: usize,
+  states: VecDeque<(usize, Vec<i64>)>,
+}
+impl History {
+  pub fn new(capacity: usize) -> Self {
+    Self {
+      capacity,
+      states: VecDeque::with_capacity(capacity),
+    }
+  }
+
+  /// Steps `computer`, remembering its state beforehand if it changed.
+  pub fn step(&mut self, computer: &mut Computer) -> Step {
+    let before = (computer.program_counter, computer.registers.clone());
+    let step = computer.step();
+    if step == Step::Running && self.capacity > 0 {
+      if self.states.len() == self.capacity {
+        self.states.pop_front();
+      }
+      self.states.push_back(before);
+    }
+    step
+  }
+
+  /// Undoes the most recent step of `computer`, if we remember it.
+  pub fn step_back(&mut self, computer: &mut Computer) -> bool {
+    match self.states.pop_back() {
+      Some((program_counter, registers)) => {
+        computer.program_counter = program_counter;
+        computer.registers = registers;
+        computer.halted = None;
+        true
+      }
+      None => false,
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    self.states.len()
   }
 }
 
@@ -184,23 +556,228 @@ mod test {
   fn examples() {
     let mut computer = Computer::new(EXAMPLE);
     computer.run_to_completion();
-    assert_eq!(42, computer.a);
+    assert_eq!(42, computer.read_register(Register::A));
   }
 
   #[test]
   fn my_input() {
     let mut computer = Computer::new(MY_INPUT);
     computer.run_to_completion();
-    assert_eq!(318020, computer.a);
+    assert_eq!(318020, computer.read_register(Register::A));
   }
 
   #[cfg(not(debug_assertions))]
   #[test]
   fn part_2_my_input() {
     let mut computer = Computer::new(MY_INPUT);
-    computer.c = 1;
+    computer.write_register(Register::C, 1);
+    computer.run_to_completion();
+    assert_eq!(9227674, computer.read_register(Register::A));
+  }
+
+  #[test]
+  fn halting() {
+    let mut computer = Computer::new(EXAMPLE);
+    assert_eq!(HaltReason::EndOfProgram, computer.run_to_completion());
+    assert_eq!(
+      Step::Halted {
+        reason: HaltReason::EndOfProgram
+      },
+      computer.step()
+    );
+
+    let mut computer = Computer::new("inc a\njnz a -2\n");
+    assert_eq!(
+      HaltReason::JumpOutOfBounds { pc: 1, target: -1 },
+      computer.run_to_completion()
+    );
+    assert_eq!(1, computer.program_counter);
+  }
+
+  #[test]
+  fn budgets() {
+    let mut computer = Computer::new(EXAMPLE);
+    assert_eq!(
+      RunResult::Halted {
+        reason: HaltReason::EndOfProgram
+      },
+      computer.run_with_budget(100)
+    );
+
+    let mut computer = Computer::new(MY_INPUT);
+    assert_eq!(RunResult::StepLimit, computer.run_with_budget(1000));
+
+    assert_eq!(
+      RunResult::Looping { period: 1 },
+      Computer::new("jnz 1 0").run_with_budget(100)
+    );
+    assert_eq!(
+      RunResult::Looping { period: 3 },
+      Computer::new("cpy 5 b\ninc a\ndec a\njnz b -2").run_with_budget(100)
+    );
+    // Loops are reported on the step where the first state repeats, however
+    // long the run up to them.
+    assert_eq!(
+      RunResult::Looping { period: 1 },
+      Computer::new("jnz 1 0").run_with_budget(1)
+    );
+    let program = "cpy 1000 b\ndec b\njnz b -1\ninc a\ndec a\njnz 1 -2";
+    assert_eq!(
+      RunResult::StepLimit,
+      Computer::new(program).run_with_budget(2003)
+    );
+    assert_eq!(
+      RunResult::Looping { period: 3 },
+      Computer::new(program).run_with_budget(2004)
+    );
+
+    // Counting up forever only repeats once the register wraps around.
+    let counter = "inc a\njnz 1 -1";
+    assert_eq!(
+      RunResult::StepLimit,
+      Computer::new(counter).run_with_budget(100_000)
+    );
+    let mut computer = Computer::with_config(
+      counter,
+      Config {
+        bits: 4,
+        ..Config::default()
+      },
+    )
+    .unwrap();
+    assert_eq!(
+      RunResult::Looping { period: 32 },
+      computer.run_with_budget(1000)
+    );
+  }
+
+  #[test]
+  fn snapshots() {
+    let mut computer = Computer::new(MY_INPUT);
+    let initial = computer.snapshot();
+    computer.run_with_budget(1000);
+    let midway = computer.snapshot();
+    computer.run_to_completion();
+    assert_eq!(318020, computer.read_register(Register::A));
+
+    let mut computer = Computer::from_snapshot(midway.clone());
     computer.run_to_completion();
-    assert_eq!(9227674, computer.a);
+    assert_eq!(318020, computer.read_register(Register::A));
+
+    let mut computer = Computer::from_snapshot(initial.clone());
+    computer.write_register(Register::C, 1);
+    computer.run_with_budget(1000);
+    assert_ne!(midway, computer.snapshot());
+
+    let path = crate::test_util::temp_path("day_12_snapshot.txt");
+    let path = path.to_str().unwrap();
+    midway.save(path).unwrap();
+    let loaded = Snapshot::load(path).unwrap();
+    std::fs::remove_file(path).unwrap();
+    assert_eq!(midway, loaded);
+    assert!(midway
+      .to_string()
+      .starts_with("config 4 64 wrap\npc 12\nregisters 318 59 233 15\nprogram\ncpy 1 a\n"));
+  }
+
+  #[test]
+  fn history() {
+    let mut computer = Computer::new(EXAMPLE);
+    let mut history = History::new(3);
+    while history.step(&mut computer) == Step::Running {}
+    assert_eq!(3, history.len());
+    assert_eq!(42, computer.read_register(Register::A));
+
+    assert!(history.step_back(&mut computer));
+    assert_eq!(
+      (4, 42),
+      (
+        computer.program_counter,
+        computer.read_register(Register::A)
+      )
+    );
+    assert!(history.step_back(&mut computer));
+    assert!(history.step_back(&mut computer));
+    assert_eq!(
+      (2, 42),
+      (
+        computer.program_counter,
+        computer.read_register(Register::A)
+      )
+    );
+    assert!(!history.step_back(&mut computer));
+
+    // The computer carries on as normal after going back in time.
+    assert_eq!(HaltReason::EndOfProgram, computer.run_to_completion());
+    assert_eq!(42, computer.read_register(Register::A));
+  }
+
+  #[test]
+  fn configs() {
+    let program = "cpy 126 e\ninc e\ninc e\ndec a\n";
+    let run = |bits, overflow| {
+      let mut computer = Computer::with_config(
+        program,
+        Config {
+          registers: 5,
+          bits,
+          overflow,
+        },
+      )
+      .unwrap();
+      let reason = computer.run_to_completion();
+      (reason, computer.registers)
+    };
+    assert_eq!(
+      (HaltReason::EndOfProgram, vec![-1, 0, 0, 0, 128]),
+      run(64, Overflow::Trap)
+    );
+    assert_eq!(
+      (HaltReason::EndOfProgram, vec![-1, 0, 0, 0, -128]),
+      run(8, Overflow::Wrap)
+    );
+    assert_eq!(
+      (HaltReason::EndOfProgram, vec![-1, 0, 0, 0, 127]),
+      run(8, Overflow::Saturate)
+    );
+    assert_eq!(
+      (HaltReason::Overflow { pc: 2 }, vec![0, 0, 0, 0, 127]),
+      run(8, Overflow::Trap)
+    );
+    assert_eq!(
+      (HaltReason::Overflow { pc: 0 }, vec![0, 0, 0, 0, 0]),
+      run(7, Overflow::Trap)
+    );
+
+    assert_eq!(
+      "Line 1 uses register e, but there are only 4",
+      Computer::with_config(program, Config::default())
+        .err()
+        .unwrap()
+        .to_string()
+    );
+  }
+
+  #[test]
+  fn parse_errors() {
+    let error = |program| {
+      Computer::with_config(program, Config::default())
+        .err()
+        .unwrap()
+        .to_string()
+    };
+    assert_eq!("Unknown instruction: add", error("inc a\nadd a b\n"));
+    assert_eq!("Couldn't parse instruction: jnz", error("jnz"));
+    assert_eq!("Couldn't parse instruction: é", error("cpy 1 a\né"));
+    assert_eq!("Couldn't parse jnz args", error("jnz a"));
+  }
+
+  #[test]
+  fn display_round_trip() {
+    let computer = Computer::new(MY_INPUT);
+    assert_eq!(MY_INPUT, format_program(&computer.instructions));
+    let reparsed = Computer::new(&format_program(&computer.instructions));
+    assert_eq!(computer.instructions, reparsed.instructions);
   }
 
   static EXAMPLE: &'static str = "\
@@ -0,0 +1,274 @@
+#![allow(dead_code)]
+
+use std::fmt::Write;
+
+use crate::day_12::{Computer, Config, Instruction, RegisterOrInteger};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Target {
+  // Index into `ControlFlowGraph::blocks`.
+  Block(usize),
+  // Control leaves the program, ending execution.
+  Exit,
+  // A jump whose offset is read from a register, so could go anywhere.
+  Unknown,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EdgeKind {
+  Always,
+  Taken,
+  NotTaken,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BasicBlock {
+  // Instruction indexes, `start..end`.
+  pub start: usize,
+  pub end: usize,
+  pub successors: Vec<(EdgeKind, Target)>,
+}
+
+/// The basic blocks of an assembunny program. Blocks start at the program
+/// entry, at every constant jump target, and after every `jnz`.
+pub struct ControlFlowGraph {
+  pub blocks: Vec<BasicBlock>,
+}
+impl ControlFlowGraph {
+  pub fn new(instructions: &[Instruction]) -> Self {
+    let len = instructions.len();
+    let mut leaders = vec![false; len + 1];
+    leaders[0] = true;
+    for (idx, instruction) in instructions.iter().enumerate() {
+      if let Instruction::JumpNotZero { offset, .. } = instruction {
+        leaders[idx + 1] = true;
+        if let Some(target) = jump_target(idx, *offset, len) {
+          leaders[target] = true;
+        }
+      }
+    }
+    let starts: Vec<usize> = (0..len).filter(|i| leaders[*i]).collect();
+    let block_at = |idx: usize| -> Target {
+      match starts.binary_search(&idx) {
+        Ok(block) => Target::Block(block),
+        Err(_) => Target::Exit,
+      }
+    };
+
+    let mut blocks = Vec::with_capacity(starts.len());
+    for (i, start) in starts.iter().enumerate() {
+      let end = starts.get(i + 1).copied().unwrap_or(len);
+      let last = end - 1;
+      let successors = match instructions[last] {
+        Instruction::JumpNotZero { test, offset } => {
+          let taken = match jump_target(last, offset, len) {
+            Some(target) => block_at(target),
+            None if matches!(offset, RegisterOrInteger::R(_)) => Target::Unknown,
+            None => Target::Exit,
+          };
+          match test {
+            RegisterOrInteger::I(0) => vec![(EdgeKind::Always, block_at(end))],
+            RegisterOrInteger::I(_) => vec![(EdgeKind::Always, taken)],
+            RegisterOrInteger::R(_) => vec![
+              (EdgeKind::Taken, taken),
+              (EdgeKind::NotTaken, block_at(end)),
+            ],
+          }
+        }
+        _ => vec![(EdgeKind::Always, block_at(end))],
+      };
+      blocks.push(BasicBlock {
+        start: *start,
+        end,
+        successors,
+      });
+    }
+    Self { blocks }
+  }
+
+  /// Renders the graph in Graphviz DOT, with each block labelled by its
+  /// line numbers and instructions.
+  pub fn to_dot(&self, instructions: &[Instruction]) -> String {
+    let mut result = String::new();
+    writeln!(result, "digraph program {{").unwrap();
+    writeln!(result, "  node [shape=box, fontname=monospace];").unwrap();
+    writeln!(result, "  entry [shape=point];").unwrap();
+    writeln!(result, "  exit [shape=doublecircle, label=\"exit\"];").unwrap();
+    if self.has_unknown_targets() {
+      writeln!(result, "  unknown [shape=diamond, label=\"?\"];").unwrap();
+    }
+    for (i, block) in self.blocks.iter().enumerate() {
+      let mut label = String::new();
+      for (idx, instruction) in instructions[block.start..block.end].iter().enumerate() {
+        write!(label, "{:>3}: {}\\l", block.start + idx + 1, instruction).unwrap();
+      }
+      writeln!(result, "  b{} [label=\"{}\"];", i, label).unwrap();
+    }
+    if !self.blocks.is_empty() {
+      writeln!(result, "  entry -> b0;").unwrap();
+    } else {
+      writeln!(result, "  entry -> exit;").unwrap();
+    }
+    for (i, block) in self.blocks.iter().enumerate() {
+      for (kind, target) in block.successors.iter() {
+        let target = match target {
+          Target::Block(b) => format!("b{}", b),
+          Target::Exit => "exit".to_string(),
+          Target::Unknown => "unknown".to_string(),
+        };
+        let test = match instructions[block.end - 1] {
+          Instruction::JumpNotZero { test, .. } => test,
+          _ => RegisterOrInteger::I(0),
+        };
+        match kind {
+          EdgeKind::Always => writeln!(result, "  b{} -> {};", i, target),
+          EdgeKind::Taken => writeln!(result, "  b{} -> {} [label=\"{} != 0\"];", i, target, test),
+          EdgeKind::NotTaken => writeln!(
+            result,
+            "  b{} -> {} [label=\"{} == 0\", style=dashed];",
+            i, target, test
+          ),
+        }
+        .unwrap();
+      }
+    }
+    writeln!(result, "}}").unwrap();
+    result
+  }
+
+  fn has_unknown_targets(&self) -> bool {
+    self
+      .blocks
+      .iter()
+      .flat_map(|b| b.successors.iter())
+      .any(|(_, target)| *target == Target::Unknown)
+  }
+}
+
+/// Where the `jnz` at `idx` goes if taken, when its offset is a constant that
+/// lands inside the program. Offsets too big to add leave the program.
+fn jump_target(idx: usize, offset: RegisterOrInteger, len: usize) -> Option<usize> {
+  match offset {
+    RegisterOrInteger::I(offset) => {
+      let target = (idx as i64).checked_add(offset)?;
+      if target >= 0 && target < len as i64 {
+        Some(target as usize)
+      } else {
+        None
+      }
+    }
+    RegisterOrInteger::R(_) => None,
+  }
+}
+
+/// Prints the control flow graph of the assembunny program at `path` as DOT.
+pub fn print_dot_file(path: &str) -> Result<(), Box<dyn std::error::Error>> {
+  let program = std::fs::read_to_string(path)?;
+  let computer = Computer::with_config(&program, Config::default())?;
+  print!(
+    "{}",
+    ControlFlowGraph::new(&computer.instructions).to_dot(&computer.instructions)
+  );
+  Ok(())
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn block_lines(cfg: &ControlFlowGraph) -> Vec<(usize, usize)> {
+    cfg.blocks.iter().map(|b| (b.start + 1, b.end)).collect()
+  }
+
+  #[test]
+  fn example() {
+    let computer = Computer::new(
+      "\
+      cpy 41 a\n\
+      inc a\n\
+      inc a\n\
+      dec a\n\
+      jnz a 2\n\
+      dec a\n\
+    ",
+    );
+    let cfg = ControlFlowGraph::new(&computer.instructions);
+    assert_eq!(vec![(1, 5), (6, 6)], block_lines(&cfg));
+    assert_eq!(
+      vec![
+        (EdgeKind::Taken, Target::Exit),
+        (EdgeKind::NotTaken, Target::Block(1))
+      ],
+      cfg.blocks[0].successors
+    );
+    assert_eq!(
+      vec![(EdgeKind::Always, Target::Exit)],
+      cfg.blocks[1].successors
+    );
+  }
+
+  #[test]
+  fn my_input() {
+    let computer = Computer::new(include_str!("day_12_input.txt"));
+    let cfg = ControlFlowGraph::new(&computer.instructions);
+    assert_eq!(
+      vec![
+        (1, 4),
+        (5, 5),
+        (6, 6),
+        (7, 9),
+        (10, 10),
+        (11, 13),
+        (14, 16),
+        (17, 17),
+        (18, 18),
+        (19, 21),
+        (22, 23)
+      ],
+      block_lines(&cfg)
+    );
+    // `jnz 1 5` always jumps, skipping `cpy 7 c`.
+    assert_eq!(
+      vec![(EdgeKind::Always, Target::Block(4))],
+      cfg.blocks[1].successors
+    );
+    // The fibonacci loop: the inner block loops on itself.
+    assert_eq!(
+      vec![
+        (EdgeKind::Taken, Target::Block(5)),
+        (EdgeKind::NotTaken, Target::Block(6))
+      ],
+      cfg.blocks[5].successors
+    );
+
+    let dot = cfg.to_dot(&computer.instructions);
+    assert!(dot.starts_with("digraph program {\n"));
+    assert!(dot.contains("  b5 [label=\" 11: inc a\\l 12: dec b\\l 13: jnz b -2\\l\"];\n"));
+    assert!(dot.contains("  b5 -> b5 [label=\"b != 0\"];\n"));
+    assert!(dot.contains("  b10 -> exit [label=\"c == 0\", style=dashed];\n"));
+    assert!(!dot.contains("unknown"));
+  }
+
+  #[test]
+  fn register_offsets() {
+    let computer = Computer::new("jnz 1 a\ninc a\n");
+    let cfg = ControlFlowGraph::new(&computer.instructions);
+    assert_eq!(
+      vec![(EdgeKind::Always, Target::Unknown)],
+      cfg.blocks[0].successors
+    );
+    assert!(cfg
+      .to_dot(&computer.instructions)
+      .contains("  b0 -> unknown;\n"));
+  }
+
+  #[test]
+  fn huge_offsets() {
+    let computer = Computer::new("inc a\njnz 1 9223372036854775807\n");
+    let cfg = ControlFlowGraph::new(&computer.instructions);
+    assert_eq!(
+      vec![(EdgeKind::Always, Target::Exit)],
+      cfg.blocks[0].successors
+    );
+  }
+}
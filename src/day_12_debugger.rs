@@ -0,0 +1,510 @@
+#![allow(dead_code)]
+
+use std::{
+  collections::BTreeSet,
+  error::Error,
+  fmt::Write as _,
+  io::{BufRead, Write},
+  str::FromStr,
+};
+
+use crate::day_12::{Computer, Config, HaltReason, History, Register, Snapshot, Step};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Command {
+  // Breakpoints are given as 1-based line numbers, like an editor shows them.
+  Break(usize),
+  Delete(usize),
+  Watch(Register),
+  Unwatch(Register),
+  Step(usize),
+  Back(usize),
+  Continue,
+  Save(String),
+  Load(String),
+  Registers,
+  List,
+  Help,
+  Quit,
+}
+impl FromStr for Command {
+  type Err = Box<dyn Error>;
+
+  fn from_str(line: &str) -> Result<Self, Self::Err> {
+    let mut words = line.split_whitespace();
+    let command = words.next().ok_or("Empty command")?;
+    let arg = words.next();
+    let result = match (command, arg) {
+      ("b", Some(line)) | ("break", Some(line)) => Command::Break(parse_line(line)?),
+      ("d", Some(line)) | ("delete", Some(line)) => Command::Delete(parse_line(line)?),
+      ("w", Some(r)) | ("watch", Some(r)) => Command::Watch(r.parse()?),
+      ("unwatch", Some(r)) => Command::Unwatch(r.parse()?),
+      ("s", None) | ("step", None) => Command::Step(1),
+      ("s", Some(n)) | ("step", Some(n)) => Command::Step(n.parse()?),
+      ("back", None) => Command::Back(1),
+      ("back", Some(n)) => Command::Back(n.parse()?),
+      ("c", None) | ("continue", None) => Command::Continue,
+      ("save", Some(path)) => Command::Save(path.to_string()),
+      ("load", Some(path)) => Command::Load(path.to_string()),
+      ("r", None) | ("regs", None) => Command::Registers,
+      ("l", None) | ("list", None) => Command::List,
+      ("h", None) | ("help", None) => Command::Help,
+      ("q", None) | ("quit", None) => Command::Quit,
+      _ => return Err(format!("Unknown command: {}", line.trim()).into()),
+    };
+    if words.next().is_some() {
+      return Err(format!("Too many arguments: {}", line.trim()).into());
+    }
+    Ok(result)
+  }
+}
+
+fn parse_line(line: &str) -> Result<usize, Box<dyn Error>> {
+  match line.parse()? {
+    0 => Err("Line numbers start at 1".into()),
+    line => Ok(line),
+  }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum StopReason {
+  Stepped,
+  Breakpoint(usize),
+  Watchpoint {
+    register: Register,
+    old: i64,
+    new: i64,
+  },
+  Halted(HaltReason),
+}
+
+struct Debugger {
+  computer: Computer,
+  // Program counters (0-based) to stop at before executing.
+  breakpoints: BTreeSet<usize>,
+  watchpoints: BTreeSet<Register>,
+  history: History,
+}
+impl Debugger {
+  fn new(program: &str) -> Result<Self, Box<dyn Error>> {
+    Ok(Self {
+      computer: Computer::with_config(program, Config::default())?,
+      breakpoints: BTreeSet::new(),
+      watchpoints: BTreeSet::new(),
+      history: History::new(HISTORY_SIZE),
+    })
+  }
+
+  /// Executes a single instruction, reporting whether a watched register
+  /// changed or the program ran off the end.
+  fn step(&mut self) -> StopReason {
+    let before = self.watched_values();
+    if let Step::Halted { reason } = self.history.step(&mut self.computer) {
+      return StopReason::Halted(reason);
+    }
+    for (register, old) in before {
+      let new = self.computer.read_register(register);
+      if new != old {
+        return StopReason::Watchpoint { register, old, new };
+      }
+    }
+    StopReason::Stepped
+  }
+
+  fn step_n(&mut self, n: usize) -> StopReason {
+    for i in 0..n {
+      let reason = self.step();
+      if reason != StopReason::Stepped {
+        return reason;
+      }
+      if i + 1 < n && self.breakpoints.contains(&self.computer.program_counter) {
+        return StopReason::Breakpoint(self.computer.program_counter);
+      }
+    }
+    StopReason::Stepped
+  }
+
+  /// Runs until a breakpoint or watchpoint triggers or the program halts.
+  /// Always executes at least one instruction, so continuing from a
+  /// breakpoint makes progress.
+  fn continue_running(&mut self) -> StopReason {
+    loop {
+      let reason = self.step();
+      if reason != StopReason::Stepped {
+        return reason;
+      }
+      if self.breakpoints.contains(&self.computer.program_counter) {
+        return StopReason::Breakpoint(self.computer.program_counter);
+      }
+    }
+  }
+
+  /// Undoes up to `n` instructions, returning how many were undone.
+  fn step_back(&mut self, n: usize) -> usize {
+    (0..n)
+      .take_while(|_| self.history.step_back(&mut self.computer))
+      .count()
+  }
+
+  /// Replaces the computer with a saved one. The history belongs to the old
+  /// computer, so is forgotten.
+  fn load(&mut self, snapshot: Snapshot) {
+    self.computer = Computer::from_snapshot(snapshot);
+    self.history = History::new(HISTORY_SIZE);
+    let len = self.computer.instructions.len();
+    self.breakpoints.retain(|pc| *pc < len);
+    let registers = self.computer.config.registers;
+    self.watchpoints.retain(|r| r.index() < registers);
+  }
+
+  fn watched_values(&self) -> Vec<(Register, i64)> {
+    self
+      .watchpoints
+      .iter()
+      .map(|r| (*r, self.computer.read_register(*r)))
+      .collect()
+  }
+
+  fn registers(&self) -> String {
+    let mut result = String::new();
+    for register in self.computer.config.registers() {
+      write!(
+        result,
+        "{}: {}  ",
+        register,
+        self.computer.read_register(register)
+      )
+      .unwrap();
+    }
+    write!(result, "pc: {}", self.computer.program_counter + 1).unwrap();
+    result
+  }
+
+  /// The instructions surrounding the program counter, with `=>` marking the
+  /// next instruction to execute and `*` marking breakpoints.
+  fn listing(&self, context: usize) -> String {
+    let pc = self.computer.program_counter;
+    let start = pc.saturating_sub(context);
+    let end = (pc + context + 1).min(self.computer.instructions.len());
+    let mut result = String::new();
+    for idx in start..end {
+      writeln!(
+        result,
+        "{}{} {:>3}: {}",
+        if idx == pc { "=>" } else { "  " },
+        if self.breakpoints.contains(&idx) {
+          "*"
+        } else {
+          " "
+        },
+        idx + 1,
+        self.computer.instructions[idx]
+      )
+      .unwrap();
+    }
+    if pc >= self.computer.instructions.len() {
+      writeln!(result, "=>  {:>3}: <end of program>", pc + 1).unwrap();
+    }
+    result
+  }
+
+  fn describe_stop(&self, reason: StopReason) -> String {
+    let mut result = match reason {
+      StopReason::Stepped => String::new(),
+      StopReason::Breakpoint(pc) => format!("Breakpoint at line {}\n", pc + 1),
+      StopReason::Watchpoint { register, old, new } => {
+        format!("Watchpoint: {} changed from {} to {}\n", register, old, new)
+      }
+      StopReason::Halted(reason) => {
+        let reason = match reason {
+          HaltReason::EndOfProgram => "Program halted".to_string(),
+          HaltReason::JumpOutOfBounds { pc, target } => format!(
+            "Program halted: line {} jumped out of bounds, to line {}",
+            pc + 1,
+            target + 1
+          ),
+          HaltReason::Overflow { pc } => format!("Program halted: overflow at line {}", pc + 1),
+        };
+        return format!("{}\n{}\n", reason, self.registers());
+      }
+    };
+    result.push_str(&self.listing(2));
+    result
+  }
+
+  /// Executes a command, returning the text to show the user.
+  fn execute(&mut self, command: Command) -> String {
+    match command {
+      Command::Break(line) => {
+        if line > self.computer.instructions.len() {
+          return format!("No instruction at line {}\n", line);
+        }
+        self.breakpoints.insert(line - 1);
+        format!("Breakpoint set at line {}\n", line)
+      }
+      Command::Delete(line) => {
+        if self.breakpoints.remove(&(line - 1)) {
+          format!("Breakpoint at line {} deleted\n", line)
+        } else {
+          format!("No breakpoint at line {}\n", line)
+        }
+      }
+      Command::Watch(register) => {
+        if register.index() >= self.computer.config.registers {
+          return format!("No register {}\n", register);
+        }
+        self.watchpoints.insert(register);
+        format!("Watching {}\n", register)
+      }
+      Command::Unwatch(register) => {
+        self.watchpoints.remove(&register);
+        format!("No longer watching {}\n", register)
+      }
+      Command::Step(n) => {
+        let reason = self.step_n(n);
+        self.describe_stop(reason)
+      }
+      Command::Back(n) => {
+        let undone = self.step_back(n);
+        let mut result = match undone {
+          0 => "No history to go back through\n".to_string(),
+          1 => "Went back 1 step\n".to_string(),
+          undone => format!("Went back {} steps\n", undone),
+        };
+        result.push_str(&self.listing(2));
+        result
+      }
+      Command::Continue => {
+        let reason = self.continue_running();
+        self.describe_stop(reason)
+      }
+      Command::Save(path) => match self.computer.snapshot().save(&path) {
+        Ok(()) => format!("Saved to {}\n", path),
+        Err(e) => format!("Couldn't save to {}: {}\n", path, e),
+      },
+      Command::Load(path) => match Snapshot::load(&path) {
+        Ok(snapshot) => {
+          self.load(snapshot);
+          format!("Loaded {}\n{}", path, self.listing(2))
+        }
+        Err(e) => format!("Couldn't load {}: {}\n", path, e),
+      },
+      Command::Registers => format!("{}\n", self.registers()),
+      Command::List => self.listing(5),
+      Command::Help => HELP.to_string(),
+      Command::Quit => String::new(),
+    }
+  }
+
+  fn run_interactive(
+    &mut self,
+    input: impl BufRead,
+    mut output: impl Write,
+  ) -> std::io::Result<()> {
+    output.write_all(self.listing(2).as_bytes())?;
+    write!(output, "(abd) ")?;
+    output.flush()?;
+    for line in input.lines() {
+      let line = line?;
+      if !line.trim().is_empty() {
+        match line.parse() {
+          Ok(Command::Quit) => return Ok(()),
+          Ok(command) => output.write_all(self.execute(command).as_bytes())?,
+          Err(e) => writeln!(output, "{}", e)?,
+        }
+      }
+      write!(output, "(abd) ")?;
+      output.flush()?;
+    }
+    Ok(())
+  }
+}
+
+// How many instructions can be undone with `back`.
+const HISTORY_SIZE: usize = 10_000;
+
+static HELP: &str = "\
+break <line>     set a breakpoint (alias: b)
+delete <line>    remove a breakpoint (alias: d)
+watch <reg>      stop when a register changes (alias: w)
+unwatch <reg>    stop watching a register
+step [n]         execute n instructions, default 1 (alias: s)
+back [n]         undo n instructions, default 1
+continue         run until a breakpoint, watchpoint or halt (alias: c)
+regs             print the registers and program counter (alias: r)
+list             show the program around the current instruction (alias: l)
+save <path>      save the computer's state to a file
+load <path>      restore the computer's state from a file
+quit             exit the debugger (alias: q)
+";
+
+/// Loads the assembunny program at `path` and debugs it interactively on
+/// stdin/stdout.
+pub fn debug_file(path: &str) -> Result<(), Box<dyn Error>> {
+  let program = std::fs::read_to_string(path)?;
+  let mut debugger = Debugger::new(&program)?;
+  let stdin = std::io::stdin();
+  debugger.run_interactive(stdin.lock(), std::io::stdout())?;
+  Ok(())
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  static EXAMPLE: &str = "\
+    cpy 41 a\n\
+    inc a\n\
+    inc a\n\
+    dec a\n\
+    jnz a 2\n\
+    dec a\n\
+  ";
+
+  #[test]
+  fn parse_commands() {
+    assert_eq!(Command::Break(3), "b 3".parse::<Command>().unwrap());
+    assert_eq!(Command::Step(1), "step".parse::<Command>().unwrap());
+    assert_eq!(Command::Step(10), "s 10".parse::<Command>().unwrap());
+    assert_eq!(
+      Command::Watch(Register::C),
+      "watch c".parse::<Command>().unwrap()
+    );
+    assert!("break 0".parse::<Command>().is_err());
+    assert!("watch 1".parse::<Command>().is_err());
+    assert!("step 1 2".parse::<Command>().is_err());
+    assert_eq!(Command::Back(1), "back".parse::<Command>().unwrap());
+    assert_eq!(
+      Command::Save("state.txt".to_string()),
+      "save state.txt".parse::<Command>().unwrap()
+    );
+    assert!("load".parse::<Command>().is_err());
+  }
+
+  #[test]
+  fn breakpoints() {
+    let mut debugger = Debugger::new(EXAMPLE).unwrap();
+    debugger.execute(Command::Break(4));
+    assert_eq!(StopReason::Breakpoint(3), debugger.continue_running());
+    assert_eq!(43, debugger.computer.read_register(Register::A));
+    assert_eq!("a: 43  b: 0  c: 0  d: 0  pc: 4", debugger.registers());
+    assert_eq!(
+      StopReason::Halted(HaltReason::EndOfProgram),
+      debugger.continue_running()
+    );
+    assert_eq!(42, debugger.computer.read_register(Register::A));
+  }
+
+  #[test]
+  fn stepping_and_listing() {
+    let mut debugger = Debugger::new(EXAMPLE).unwrap();
+    debugger.execute(Command::Break(3));
+    assert_eq!(StopReason::Stepped, debugger.step_n(1));
+    assert_eq!(
+      "      1: cpy 41 a\n\
+       =>    2: inc a\n  \
+       *   3: inc a\n",
+      debugger.listing(1)
+    );
+    // Stepping over a breakpoint stops at it rather than running past.
+    assert_eq!(StopReason::Breakpoint(2), debugger.step_n(5));
+  }
+
+  #[test]
+  fn watchpoints() {
+    let mut debugger = Debugger::new(EXAMPLE).unwrap();
+    assert_eq!(
+      "No register e\n",
+      debugger.execute(Command::Watch("e".parse().unwrap()))
+    );
+    debugger.execute(Command::Watch(Register::A));
+    assert_eq!(
+      StopReason::Watchpoint {
+        register: Register::A,
+        old: 0,
+        new: 41
+      },
+      debugger.continue_running()
+    );
+    assert_eq!(
+      StopReason::Watchpoint {
+        register: Register::A,
+        old: 41,
+        new: 42
+      },
+      debugger.continue_running()
+    );
+    debugger.execute(Command::Unwatch(Register::A));
+    assert_eq!(
+      StopReason::Halted(HaltReason::EndOfProgram),
+      debugger.continue_running()
+    );
+  }
+
+  #[test]
+  fn reverse_stepping() {
+    let mut debugger = Debugger::new(EXAMPLE).unwrap();
+    debugger.execute(Command::Break(5));
+    debugger.continue_running();
+    assert_eq!(42, debugger.computer.read_register(Register::A));
+    assert_eq!(
+      "Went back 2 steps\n\
+       \x20     1: cpy 41 a\n\
+       \x20     2: inc a\n\
+       =>    3: inc a\n\
+       \x20     4: dec a\n\
+       \x20 *   5: jnz a 2\n",
+      debugger.execute(Command::Back(2))
+    );
+    assert_eq!(42, debugger.computer.read_register(Register::A));
+    assert_eq!(2, debugger.step_back(5));
+    assert_eq!("a: 0  b: 0  c: 0  d: 0  pc: 1", debugger.registers());
+    assert!(debugger
+      .execute(Command::Back(1))
+      .starts_with("No history to go back through\n"));
+    // Running forwards again retraces the same steps.
+    assert_eq!(StopReason::Breakpoint(4), debugger.continue_running());
+    assert_eq!(42, debugger.computer.read_register(Register::A));
+  }
+
+  #[test]
+  fn save_and_load() {
+    let path = crate::test_util::temp_path("day_12_debugger.txt");
+    let path = path.to_str().unwrap().to_string();
+    let mut debugger = Debugger::new(EXAMPLE).unwrap();
+    debugger.step_n(3);
+    assert_eq!(
+      format!("Saved to {}\n", path),
+      debugger.execute(Command::Save(path.clone()))
+    );
+    debugger.continue_running();
+    assert!(debugger
+      .execute(Command::Load(path.clone()))
+      .starts_with(&format!("Loaded {}\n", path)));
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!("a: 43  b: 0  c: 0  d: 0  pc: 4", debugger.registers());
+    assert_eq!(0, debugger.step_back(1));
+    assert_eq!(
+      StopReason::Halted(HaltReason::EndOfProgram),
+      debugger.continue_running()
+    );
+    assert!(debugger
+      .execute(Command::Load(path.clone()))
+      .starts_with("Couldn't load"));
+  }
+
+  #[test]
+  fn interactive_session() {
+    let mut debugger = Debugger::new(EXAMPLE).unwrap();
+    let mut output = Vec::new();
+    debugger
+      .run_interactive("b 5\nc\nregs\nbogus\nq\nregs\n".as_bytes(), &mut output)
+      .unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("Breakpoint at line 5"));
+    assert!(output.contains("a: 42  b: 0  c: 0  d: 0  pc: 5"));
+    assert!(output.contains("Unknown command: bogus"));
+    // Nothing is executed after quitting.
+    assert_eq!(1, output.matches("pc: 5").count());
+  }
+}
@@ -0,0 +1,782 @@
+#![allow(dead_code)]
+
+use std::{
+  collections::{BTreeMap, BTreeSet},
+  error::Error,
+  fmt::Display,
+};
+
+use crate::day_12::{Computer, Config, Instruction, Register, RegisterOrInteger};
+
+/// A symbolic value. Build these with the functions below (`add`, `mul`,
+/// etc), which keep them in a canonical, simplified form.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Expr {
+  Const(i64),
+  // The value a register held at the start of the program.
+  Reg(Register),
+  Add(Box<Expr>, Box<Expr>),
+  Mul(Box<Expr>, Box<Expr>),
+  // The nth fibonacci number, with fib(0) = 0 and fib(1) = 1. Kept symbolic
+  // even when n is known, since that's much more informative than the
+  // number it evaluates to.
+  Fib(Box<Expr>),
+  // `test ? then : otherwise`, with the test being whether `test` != 0.
+  Select(Box<Expr>, Box<Expr>, Box<Expr>),
+}
+use Expr::*;
+
+impl Expr {
+  /// The value of this expression when the registers start out holding
+  /// `inputs`, or None if that takes the fibonacci number of a negative
+  /// number.
+  pub fn evaluate(&self, inputs: &[i64]) -> Option<i64> {
+    match self {
+      Const(k) => Some(*k),
+      Reg(r) => Some(inputs[r.index()]),
+      Add(l, r) => Some(l.evaluate(inputs)?.wrapping_add(r.evaluate(inputs)?)),
+      Mul(l, r) => Some(l.evaluate(inputs)?.wrapping_mul(r.evaluate(inputs)?)),
+      Fib(n) => fibonacci(n.evaluate(inputs)?),
+      Select(test, then, otherwise) => {
+        if test.evaluate(inputs)? != 0 {
+          then.evaluate(inputs)
+        } else {
+          otherwise.evaluate(inputs)
+        }
+      }
+    }
+  }
+
+  /// The value of this expression if it doesn't depend on any inputs.
+  fn constant_value(&self) -> Option<i64> {
+    if self.registers().is_empty() {
+      self.evaluate(&[])
+    } else {
+      None
+    }
+  }
+
+  fn registers(&self) -> BTreeSet<Register> {
+    let mut result = BTreeSet::new();
+    self.collect_registers(&mut result);
+    result
+  }
+
+  fn collect_registers(&self, result: &mut BTreeSet<Register>) {
+    match self {
+      Const(_) => {}
+      Reg(r) => {
+        result.insert(*r);
+      }
+      Add(l, r) | Mul(l, r) => {
+        l.collect_registers(result);
+        r.collect_registers(result);
+      }
+      Fib(n) => n.collect_registers(result),
+      Select(test, then, otherwise) => {
+        test.collect_registers(result);
+        then.collect_registers(result);
+        otherwise.collect_registers(result);
+      }
+    }
+  }
+
+  /// Replaces every register with its value in `state`.
+  fn substitute(&self, state: &State) -> Expr {
+    match self {
+      Const(k) => Const(*k),
+      Reg(r) => state[r.index()].clone(),
+      Add(l, r) => add(l.substitute(state), r.substitute(state)),
+      Mul(l, r) => mul(l.substitute(state), r.substitute(state)),
+      Fib(n) => fib(n.substitute(state)),
+      Select(test, then, otherwise) => select(
+        test.substitute(state),
+        then.substitute(state),
+        otherwise.substitute(state),
+      ),
+    }
+  }
+
+  fn fmt_operand(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Add(..) | Select(..) => write!(f, "({})", self),
+      Const(k) if *k < 0 => write!(f, "({})", self),
+      _ => write!(f, "{}", self),
+    }
+  }
+}
+impl Display for Expr {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Const(k) => write!(f, "{}", k),
+      Reg(r) => write!(f, "{}", r),
+      Add(..) => {
+        let (constant, terms) = linear_terms(self);
+        let mut first = true;
+        for (term, coefficient) in terms {
+          let magnitude = if first {
+            coefficient
+          } else {
+            f.write_str(if coefficient < 0 { " - " } else { " + " })?;
+            coefficient.abs()
+          };
+          match magnitude {
+            1 => term.fmt_operand(f)?,
+            -1 => {
+              f.write_str("-")?;
+              term.fmt_operand(f)?;
+            }
+            m => {
+              write!(f, "{}*", m)?;
+              term.fmt_operand(f)?;
+            }
+          }
+          first = false;
+        }
+        match constant {
+          0 => Ok(()),
+          k if k < 0 => write!(f, " - {}", -k),
+          k => write!(f, " + {}", k),
+        }
+      }
+      Mul(l, r) => {
+        if **l == Const(-1) {
+          f.write_str("-")?;
+        } else {
+          l.fmt_operand(f)?;
+          f.write_str("*")?;
+        }
+        r.fmt_operand(f)
+      }
+      Fib(n) => write!(f, "fib({})", n),
+      Select(test, then, otherwise) => {
+        test.fmt_operand(f)?;
+        f.write_str(" ? ")?;
+        then.fmt_operand(f)?;
+        f.write_str(" : ")?;
+        otherwise.fmt_operand(f)
+      }
+    }
+  }
+}
+
+fn fibonacci(n: i64) -> Option<i64> {
+  if n < 0 {
+    return None;
+  }
+  // Fast doubling, so that huge loop counts are still cheap to evaluate.
+  fn fib_pair(n: u64) -> (i64, i64) {
+    if n == 0 {
+      return (0, 1);
+    }
+    let (a, b) = fib_pair(n / 2);
+    let c = a.wrapping_mul(b.wrapping_mul(2).wrapping_sub(a));
+    let d = a.wrapping_mul(a).wrapping_add(b.wrapping_mul(b));
+    if n & 1 == 0 {
+      (c, d)
+    } else {
+      (d, c.wrapping_add(d))
+    }
+  }
+  Some(fib_pair(n as u64).0)
+}
+
+/// Splits a sum into its constant part and the coefficient of each other
+/// term.
+fn linear_terms(e: &Expr) -> (i64, BTreeMap<Expr, i64>) {
+  let mut terms = BTreeMap::new();
+  let constant = collect_terms(e, 1, &mut terms);
+  terms.retain(|_, coefficient| *coefficient != 0);
+  (constant, terms)
+}
+
+fn collect_terms(e: &Expr, scale: i64, terms: &mut BTreeMap<Expr, i64>) -> i64 {
+  match e {
+    Const(k) => return k.wrapping_mul(scale),
+    Add(l, r) => {
+      return collect_terms(l, scale, terms).wrapping_add(collect_terms(r, scale, terms));
+    }
+    Mul(l, r) => {
+      if let Const(k) = **l {
+        return collect_terms(r, scale.wrapping_mul(k), terms);
+      }
+    }
+    _ => {}
+  }
+  let coefficient = terms.entry(e.clone()).or_insert(0);
+  *coefficient = coefficient.wrapping_add(scale);
+  0
+}
+
+fn from_linear_terms(constant: i64, mut terms: BTreeMap<Expr, i64>) -> Expr {
+  terms.retain(|_, coefficient| *coefficient != 0);
+  // fib(n) + fib(n + 1) = fib(n + 2)
+  while let Some((n, coefficient)) = terms.iter().find_map(|(term, coefficient)| match term {
+    Fib(n) if terms.get(&fib(add(*n.clone(), Const(1)))) == Some(coefficient) => {
+      Some((*n.clone(), *coefficient))
+    }
+    _ => None,
+  }) {
+    terms.remove(&fib(n.clone()));
+    terms.remove(&fib(add(n.clone(), Const(1))));
+    let sum = terms.entry(fib(add(n, Const(2)))).or_insert(0);
+    *sum = sum.wrapping_add(coefficient);
+  }
+  let mut result: Option<Expr> = None;
+  for (term, coefficient) in terms {
+    let term = if coefficient == 1 {
+      term
+    } else {
+      Mul(Box::new(Const(coefficient)), Box::new(term))
+    };
+    result = Some(match result {
+      None => term,
+      Some(sum) => Add(Box::new(sum), Box::new(term)),
+    });
+  }
+  match (result, constant) {
+    (None, k) => Const(k),
+    (Some(sum), 0) => sum,
+    (Some(sum), k) => Add(Box::new(sum), Box::new(Const(k))),
+  }
+}
+
+pub fn add(l: Expr, r: Expr) -> Expr {
+  let (l_constant, mut terms) = linear_terms(&l);
+  let (r_constant, r_terms) = linear_terms(&r);
+  for (term, coefficient) in r_terms {
+    let entry = terms.entry(term).or_insert(0);
+    *entry = entry.wrapping_add(coefficient);
+  }
+  from_linear_terms(l_constant.wrapping_add(r_constant), terms)
+}
+
+pub fn mul(l: Expr, r: Expr) -> Expr {
+  if matches!(r, Const(_)) && !matches!(l, Const(_)) {
+    return mul(r, l);
+  }
+  if let Const(k) = l {
+    let (constant, mut terms) = linear_terms(&r);
+    terms.values_mut().for_each(|c| *c = c.wrapping_mul(k));
+    return from_linear_terms(constant.wrapping_mul(k), terms);
+  }
+  // Distribute over sums, so that like terms can be collected.
+  for (sum, other) in [(&l, &r), (&r, &l)].iter() {
+    if let Add(..) = sum {
+      let (constant, terms) = linear_terms(sum);
+      return terms.into_iter().fold(
+        mul(Const(constant), (*other).clone()),
+        |total, (term, coefficient)| {
+          add(total, mul(Const(coefficient), mul(term, (*other).clone())))
+        },
+      );
+    }
+  }
+  // Otherwise it's a product of (possibly scaled) terms.
+  let mut coefficient = 1;
+  let mut factors = Vec::new();
+  collect_factors(l, &mut coefficient, &mut factors);
+  collect_factors(r, &mut coefficient, &mut factors);
+  factors.sort();
+  let mut factors = factors.into_iter();
+  let first = factors.next().unwrap();
+  let product = factors.fold(first, |product, factor| {
+    Mul(Box::new(product), Box::new(factor))
+  });
+  mul(Const(coefficient), product)
+}
+
+fn collect_factors(e: Expr, coefficient: &mut i64, factors: &mut Vec<Expr>) {
+  match e {
+    Const(k) => *coefficient = coefficient.wrapping_mul(k),
+    Mul(l, r) => {
+      collect_factors(*l, coefficient, factors);
+      collect_factors(*r, coefficient, factors);
+    }
+    e => factors.push(e),
+  }
+}
+
+pub fn fib(n: Expr) -> Expr {
+  Fib(Box::new(n))
+}
+
+pub fn select(test: Expr, then: Expr, otherwise: Expr) -> Expr {
+  if let Some(test) = test.constant_value() {
+    return if test != 0 { then } else { otherwise };
+  }
+  if then == otherwise {
+    return then;
+  }
+  // Pull out anything both branches have in common.
+  let (then_constant, mut then_terms) = linear_terms(&then);
+  let (otherwise_constant, mut otherwise_terms) = linear_terms(&otherwise);
+  let mut common = BTreeMap::new();
+  for (term, coefficient) in then_terms.iter() {
+    if otherwise_terms.get(term) == Some(coefficient) {
+      common.insert(term.clone(), *coefficient);
+    }
+  }
+  let common_constant = if then_constant == otherwise_constant {
+    then_constant
+  } else {
+    0
+  };
+  if !common.is_empty() || common_constant != 0 {
+    for term in common.keys() {
+      then_terms.remove(term);
+      otherwise_terms.remove(term);
+    }
+    return add(
+      from_linear_terms(common_constant, common),
+      select(
+        test,
+        from_linear_terms(then_constant - common_constant, then_terms),
+        from_linear_terms(otherwise_constant - common_constant, otherwise_terms),
+      ),
+    );
+  }
+  if let (Fib(then), Fib(otherwise)) = (&then, &otherwise) {
+    return fib(select(test, *then.clone(), *otherwise.clone()));
+  }
+  Select(Box::new(test), Box::new(then), Box::new(otherwise))
+}
+
+type State = Vec<Expr>;
+
+fn initial_state(registers: usize) -> State {
+  Register::all(registers).map(Reg).collect()
+}
+
+/// A `jnz r -n` loop: the instructions `start..=end`, where `end` is the
+/// jump and `counter` is the register it tests.
+#[derive(Debug, Copy, Clone)]
+struct Loop {
+  start: usize,
+  end: usize,
+  counter: Register,
+}
+
+const MAX_STEPS: u64 = 1_000_000;
+
+struct Analysis<'a> {
+  instructions: &'a [Instruction],
+  registers: usize,
+  loops: Vec<Loop>,
+  // Keyed by the loop's jump instruction.
+  summaries: BTreeMap<usize, Result<State, String>>,
+  steps: u64,
+}
+impl<'a> Analysis<'a> {
+  fn new(instructions: &'a [Instruction], registers: usize) -> Self {
+    let loops = instructions
+      .iter()
+      .enumerate()
+      .filter_map(|(end, instruction)| match instruction {
+        Instruction::JumpNotZero {
+          test: RegisterOrInteger::R(counter),
+          offset: RegisterOrInteger::I(offset),
+        } if *offset < 0 && end as i64 + offset >= 0 => Some(Loop {
+          start: (end as i64 + offset) as usize,
+          end,
+          counter: *counter,
+        }),
+        _ => None,
+      })
+      .collect();
+    Self {
+      instructions,
+      registers,
+      loops,
+      summaries: BTreeMap::new(),
+      steps: 0,
+    }
+  }
+
+  /// Symbolically executes from `pc` until control reaches `exit`. Jumps
+  /// outside of `start..=exit` halt the program when `top_level`, and are
+  /// errors otherwise. Jumps that depend on an unknown value explore both
+  /// possibilities.
+  fn execute(
+    &mut self,
+    mut pc: usize,
+    mut state: State,
+    start: usize,
+    exit: usize,
+    top_level: bool,
+  ) -> Result<State, Box<dyn Error>> {
+    while pc != exit {
+      self.steps += 1;
+      if self.steps > MAX_STEPS {
+        return Err("Gave up after too many steps without finding a closed form".into());
+      }
+      if let Some(l) = self.outermost_loop_at(pc, exit) {
+        match self.summarize(l) {
+          Ok(summary) => {
+            if let Some(n) = state[l.counter.index()].constant_value() {
+              if n <= 0 {
+                return Err(
+                  format!(
+                    "The loop at lines {}-{} never terminates",
+                    l.start + 1,
+                    l.end + 1
+                  )
+                  .into(),
+                );
+              }
+            }
+            state = summary.iter().map(|e| e.substitute(&state)).collect();
+            pc = l.end + 1;
+            continue;
+          }
+          // We can still step through a loop we don't understand, as long
+          // as we know exactly what it's operating on.
+          Err(_) if state.iter().all(|e| e.constant_value().is_some()) => {}
+          Err(e) => return Err(e.into()),
+        }
+      }
+
+      match self.instructions[pc] {
+        Instruction::Copy { from, to } => state[to.index()] = value(&state, from),
+        Instruction::Increment(r) => state[r.index()] = add(state[r.index()].clone(), Const(1)),
+        Instruction::Decrement(r) => state[r.index()] = add(state[r.index()].clone(), Const(-1)),
+        Instruction::JumpNotZero { test, offset } => {
+          let test = value(&state, test);
+          if test.constant_value() == Some(0) {
+            pc += 1;
+            continue;
+          }
+          let offset = match value(&state, offset).constant_value() {
+            Some(offset) => offset,
+            None => return Err(format!("Jump with unknown offset at line {}", pc + 1).into()),
+          };
+          // A jump too far to represent leaves the program, like any other
+          // out of bounds jump.
+          let target = (pc as i64).checked_add(offset).unwrap_or(i64::MAX);
+          let in_region = target >= start as i64 && target <= exit as i64;
+          if !in_region && !top_level {
+            return Err(format!("Jump out of loop body at line {}", pc + 1).into());
+          }
+          if test.constant_value().is_some() {
+            if !in_region {
+              return Ok(state);
+            }
+            pc = target as usize;
+            continue;
+          }
+          // Recognised loops were summarized when we reached their start, so
+          // exploring any other jump backwards would never finish.
+          if in_region && target <= pc as i64 {
+            return Err(format!("Jump back with unknown test at line {}", pc + 1).into());
+          }
+          let taken = if in_region {
+            self.execute(target as usize, state.clone(), start, exit, top_level)?
+          } else {
+            state.clone()
+          };
+          let not_taken = self.execute(pc + 1, state, start, exit, top_level)?;
+          return Ok(
+            taken
+              .into_iter()
+              .zip(not_taken)
+              .map(|(taken, not_taken)| select(test.clone(), taken, not_taken))
+              .collect(),
+          );
+        }
+      }
+      pc += 1;
+    }
+    Ok(state)
+  }
+
+  /// The biggest loop that starts at `pc` and is nested inside the region
+  /// ending at `exit`.
+  fn outermost_loop_at(&self, pc: usize, exit: usize) -> Option<Loop> {
+    self
+      .loops
+      .iter()
+      .filter(|l| l.start == pc && l.end < exit)
+      .max_by_key(|l| l.end)
+      .copied()
+  }
+
+  /// The registers after `l` runs, in terms of their values before it.
+  fn summarize(&mut self, l: Loop) -> Result<State, String> {
+    if let Some(summary) = self.summaries.get(&l.end) {
+      return summary.clone();
+    }
+    let summary = self.summarize_uncached(l);
+    self.summaries.insert(l.end, summary.clone());
+    summary
+  }
+
+  fn summarize_uncached(&mut self, l: Loop) -> Result<State, String> {
+    let lines = format!("lines {}-{}", l.start + 1, l.end + 1);
+    let body = self
+      .execute(
+        l.start,
+        initial_state(self.registers),
+        l.start,
+        l.end,
+        false,
+      )
+      .map_err(|e| e.to_string())?;
+    let counter = Reg(l.counter);
+    if body[l.counter.index()] != add(counter.clone(), Const(-1)) {
+      return Err(format!(
+        "The loop at {} doesn't count {} down by one",
+        lines, l.counter
+      ));
+    }
+    let closed_form = ClosedForm {
+      body: &body,
+      counter: l.counter,
+    };
+    Register::all(self.registers)
+      .map(|register| {
+        closed_form
+          .after(register, &counter, true, &mut BTreeSet::new())
+          .ok_or_else(|| format!("No closed form for {} in the loop at {}", register, lines))
+      })
+      .collect()
+  }
+}
+
+/// Solves for the registers after a loop body has run some number of times.
+struct ClosedForm<'a> {
+  // What one iteration does to each register.
+  body: &'a State,
+  counter: Register,
+}
+impl<'a> ClosedForm<'a> {
+  /// The value of `register` after `n` iterations, where `n` may only be 0
+  /// if `at_least_once` is false.
+  fn after(
+    &self,
+    register: Register,
+    n: &Expr,
+    at_least_once: bool,
+    visiting: &mut BTreeSet<Register>,
+  ) -> Option<Expr> {
+    let before = Reg(register);
+    let once = &self.body[register.index()];
+    if *once == before {
+      return Some(before);
+    }
+    if register == self.counter {
+      return Some(add(before, mul(Const(-1), n.clone())));
+    }
+    let modified: BTreeSet<Register> = Register::all(self.body.len())
+      .filter(|r| self.body[r.index()] != Reg(*r))
+      .collect();
+    let increment = add(once.clone(), mul(Const(-1), before.clone()));
+    let increment_registers = increment.registers();
+    if increment_registers.is_disjoint(&modified) {
+      // x += k
+      return Some(add(before, mul(n.clone(), increment)));
+    }
+    if at_least_once && once.registers().is_disjoint(&modified) {
+      // x = k
+      return Some(once.clone());
+    }
+    if let Reg(other) = increment {
+      // (x, y) = (x + y, x)
+      if self.body[other.index()] == before && other != self.counter {
+        let n_plus_one = add(n.clone(), Const(1));
+        return Some(add(
+          mul(fib(n_plus_one), before),
+          mul(fib(n.clone()), Reg(other)),
+        ));
+      }
+    }
+    if let Reg(other) = once {
+      // x = y, so x ends up with y's value from the previous iteration.
+      if at_least_once && visiting.insert(register) {
+        return self.after(*other, &add(n.clone(), Const(-1)), false, visiting);
+      }
+    }
+    None
+  }
+}
+
+fn value(state: &State, v: RegisterOrInteger) -> Expr {
+  match v {
+    RegisterOrInteger::I(i) => Const(i),
+    RegisterOrInteger::R(r) => state[r.index()].clone(),
+  }
+}
+
+/// Closed form expressions for the registers after a program finishes.
+pub struct Decompiled {
+  pub registers: Vec<Expr>,
+}
+impl Decompiled {
+  pub fn register(&self, register: Register) -> &Expr {
+    &self.registers[register.index()]
+  }
+}
+impl Display for Decompiled {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    for register in Register::all(self.registers.len()) {
+      writeln!(f, "{} = {}", register, self.register(register))?;
+    }
+    Ok(())
+  }
+}
+
+/// Symbolically executes a program, summarizing `jnz` loops as closed form
+/// expressions instead of running them. There's a register for each of
+/// `inputs`: those given a starting value are treated as constants, the rest
+/// are left as unknowns. Loop counters that are unknown are assumed to be
+/// positive, and arithmetic is assumed to be 64 bit and wrapping.
+pub fn decompile(
+  instructions: &[Instruction],
+  inputs: &[Option<i64>],
+) -> Result<Decompiled, Box<dyn Error>> {
+  let state = inputs
+    .iter()
+    .zip(Register::all(inputs.len()))
+    .map(|(input, register)| match input {
+      Some(v) => Const(*v),
+      None => Reg(register),
+    })
+    .collect();
+  let registers =
+    Analysis::new(instructions, inputs.len()).execute(0, state, 0, instructions.len(), true)?;
+  Ok(Decompiled { registers })
+}
+
+/// Prints closed forms for the registers after running the assembunny
+/// program at `path`, leaving their starting values unknown.
+pub fn print_decompiled_file(path: &str) -> Result<(), Box<dyn Error>> {
+  let program = std::fs::read_to_string(path)?;
+  let computer = Computer::with_config(&program, Config::default())?;
+  let inputs = vec![None; computer.config.registers];
+  print!("{}", decompile(&computer.instructions, &inputs)?);
+  Ok(())
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  static MY_INPUT: &str = include_str!("day_12_input.txt");
+
+  fn decompile_str(program: &str, inputs: &[Option<i64>]) -> Decompiled {
+    decompile(&Computer::new(program).instructions, inputs).unwrap()
+  }
+
+  #[test]
+  fn simplification() {
+    let a = || Reg(Register::A);
+    let b = || Reg(Register::B);
+    assert_eq!(Const(0), add(a(), mul(Const(-1), a())));
+    assert_eq!(
+      "2*a + b - 3",
+      format!("{}", add(add(a(), b()), add(a(), Const(-3))))
+    );
+    assert_eq!("2*b + a*b", format!("{}", mul(add(a(), Const(2)), b())));
+    assert_eq!(
+      "fib(a + 3)",
+      format!("{}", add(fib(add(a(), Const(2))), fib(add(a(), Const(1)))))
+    );
+    assert_eq!(
+      "a + (b ? 2 : 1)",
+      format!("{}", select(b(), add(a(), Const(2)), add(a(), Const(1))))
+    );
+    assert_eq!(Const(4), select(Const(1), Const(4), b()));
+    assert_eq!(Some(55), fib(Const(10)).evaluate(&[0; 4]));
+    assert_eq!(None, fib(add(a(), Const(-3))).evaluate(&[2, 0, 0, 0]));
+    assert_eq!(
+      "fib(-1) ? 1 : 2",
+      format!("{}", select(fib(Const(-1)), Const(1), Const(2)))
+    );
+  }
+
+  #[test]
+  fn example() {
+    let decompiled = decompile_str(
+      "cpy 41 a\ninc a\ninc a\ndec a\njnz a 2\ndec a\n",
+      &[Some(0); 4],
+    );
+    assert_eq!(&Const(42), decompiled.register(Register::A));
+  }
+
+  #[test]
+  fn multiplication() {
+    let decompiled = decompile_str(
+      "\
+      cpy b c\n\
+      inc a\n\
+      dec c\n\
+      jnz c -2\n\
+      dec d\n\
+      jnz d -5\n\
+    ",
+      &[None; 4],
+    );
+    assert_eq!(
+      "a = a + b*d\nb = b\nc = 0\nd = 0\n",
+      format!("{}", decompiled)
+    );
+  }
+
+  #[test]
+  fn my_input() {
+    let decompiled = decompile_str(MY_INPUT, &[Some(0), Some(0), None, Some(0)]);
+    assert_eq!(
+      "a = fib(c ? 35 : 28) + 209\n\
+       b = fib(c ? 34 : 27)\n\
+       c = 0\n\
+       d = 0\n",
+      format!("{}", decompiled)
+    );
+    let a = decompiled.register(Register::A);
+    assert_eq!(Some(318020), a.evaluate(&[0, 0, 0, 0]));
+    assert_eq!(Some(9227674), a.evaluate(&[0, 0, 1, 0]));
+
+    let mut computer = Computer::new(MY_INPUT);
+    computer.run_to_completion();
+    assert_eq!(
+      computer.registers,
+      decompiled
+        .registers
+        .iter()
+        .map(|e| e.evaluate(&[0; 4]).unwrap())
+        .collect::<Vec<_>>()
+    );
+  }
+
+  #[test]
+  fn non_terminating_loop() {
+    let error = decompile(
+      &Computer::new("inc a\ndec b\njnz b -2\n").instructions,
+      &[Some(0); 4],
+    )
+    .err()
+    .unwrap();
+    assert_eq!("The loop at lines 1-3 never terminates", error.to_string());
+  }
+
+  #[test]
+  fn unknown_jumps() {
+    let error = |program| {
+      decompile(&Computer::new(program).instructions, &[None; 4])
+        .err()
+        .unwrap()
+        .to_string()
+    };
+    assert_eq!("Jump back with unknown test at line 1", error("jnz a 0\n"));
+    assert_eq!(
+      "Jump back with unknown test at line 2",
+      error("cpy -1 b\njnz a b\n")
+    );
+    assert_eq!(
+      "a = a\nb = b + (a ? 0 : 1)\nc = c\nd = d\n",
+      format!(
+        "{}",
+        decompile_str("jnz a 9223372036854775807\ninc b\n", &[None; 4])
+      )
+    );
+  }
+}
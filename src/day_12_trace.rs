@@ -0,0 +1,233 @@
+#![allow(dead_code)]
+
+use std::{
+  collections::BTreeMap,
+  fmt::Write as _,
+  fs::File,
+  io::{BufWriter, Write},
+};
+
+use crate::day_12::{Computer, Config, Instruction, Step};
+
+/// Observes a `Computer` as it runs, counting how often each instruction
+/// executes and which backwards jumps (i.e. loops) are taken.
+pub struct Tracer {
+  counts: Vec<u64>,
+  // (jump instruction, target) to number of times the jump was taken.
+  back_edges: BTreeMap<(usize, usize), u64>,
+  steps: u64,
+  trace: Option<Box<dyn Write>>,
+}
+impl Tracer {
+  pub fn new(computer: &Computer) -> Self {
+    Self {
+      counts: vec![0; computer.instructions.len()],
+      back_edges: BTreeMap::new(),
+      steps: 0,
+      trace: None,
+    }
+  }
+
+  /// Also writes a line per executed instruction to `path`, with the step
+  /// number, line number, instruction and the registers after it ran,
+  /// separated by tabs.
+  pub fn with_trace_file(self, path: &str) -> std::io::Result<Self> {
+    Ok(self.with_trace_output(Box::new(BufWriter::new(File::create(path)?))))
+  }
+
+  pub fn with_trace_output(mut self, output: Box<dyn Write>) -> Self {
+    self.trace = Some(output);
+    self
+  }
+
+  pub fn step(&mut self, computer: &mut Computer) -> std::io::Result<bool> {
+    let pc = computer.program_counter;
+    let instruction = match computer.instructions.get(pc) {
+      Some(i) => *i,
+      None => return Ok(false),
+    };
+    if let Step::Halted { .. } = computer.step() {
+      return Ok(false);
+    }
+    self.steps += 1;
+    self.counts[pc] += 1;
+    if let Instruction::JumpNotZero { .. } = instruction {
+      if computer.program_counter <= pc {
+        *self
+          .back_edges
+          .entry((pc, computer.program_counter))
+          .or_insert(0) += 1;
+      }
+    }
+    if let Some(trace) = &mut self.trace {
+      write!(trace, "{}\t{}\t{}", self.steps, pc + 1, instruction)?;
+      for value in computer.registers.iter() {
+        write!(trace, "\t{}", value)?;
+      }
+      writeln!(trace)?;
+    }
+    Ok(true)
+  }
+
+  pub fn run_to_completion(&mut self, computer: &mut Computer) -> std::io::Result<()> {
+    while self.step(computer)? {}
+    if let Some(trace) = &mut self.trace {
+      trace.flush()?;
+    }
+    Ok(())
+  }
+
+  pub fn count(&self, idx: usize) -> u64 {
+    self.counts[idx]
+  }
+
+  /// The `n` most executed instruction indexes, most executed first.
+  pub fn hottest(&self, n: usize) -> Vec<(usize, u64)> {
+    let mut by_count: Vec<(usize, u64)> = self
+      .counts
+      .iter()
+      .copied()
+      .enumerate()
+      .filter(|(_, count)| *count > 0)
+      .collect();
+    by_count.sort_by(|(l_idx, l), (r_idx, r)| r.cmp(l).then(l_idx.cmp(r_idx)));
+    by_count.truncate(n);
+    by_count
+  }
+
+  /// Backwards jumps taken, as `(from, to, times)`, most taken first.
+  pub fn hot_loops(&self) -> Vec<(usize, usize, u64)> {
+    let mut loops: Vec<_> = self
+      .back_edges
+      .iter()
+      .map(|((from, to), count)| (*from, *to, *count))
+      .collect();
+    loops.sort_by(|l, r| r.2.cmp(&l.2).then((l.0, l.1).cmp(&(r.0, r.1))));
+    loops
+  }
+
+  /// The program listing with execution counts, with the `top_n` hottest
+  /// instructions ranked in the margin, followed by the hot loops.
+  pub fn summary(&self, instructions: &[Instruction], top_n: usize) -> String {
+    let ranks: BTreeMap<usize, usize> = self
+      .hottest(top_n)
+      .into_iter()
+      .enumerate()
+      .map(|(rank, (idx, _))| (idx, rank + 1))
+      .collect();
+    let mut result = String::new();
+    writeln!(result, "{} steps", self.steps).unwrap();
+    for (idx, instruction) in instructions.iter().enumerate() {
+      let rank = match ranks.get(&idx) {
+        Some(rank) => format!("#{}", rank),
+        None => String::new(),
+      };
+      writeln!(
+        result,
+        "{:>4} {:>4} {:>12}  {}",
+        rank,
+        idx + 1,
+        self.counts[idx],
+        instruction
+      )
+      .unwrap();
+    }
+    let loops = self.hot_loops();
+    if !loops.is_empty() {
+      writeln!(result, "Hot loops:").unwrap();
+      for (from, to, count) in loops {
+        writeln!(result, "  {:>4} -> {:<4} {} times", from + 1, to + 1, count).unwrap();
+      }
+    }
+    result
+  }
+}
+
+/// Runs the assembunny program at `path` under a tracer and prints a profile,
+/// optionally writing the full execution trace to `trace_path`.
+pub fn profile_file(
+  path: &str,
+  trace_path: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+  let program = std::fs::read_to_string(path)?;
+  let mut computer = Computer::with_config(&program, Config::default())?;
+  let mut tracer = Tracer::new(&computer);
+  if let Some(trace_path) = trace_path {
+    tracer = tracer.with_trace_file(trace_path)?;
+  }
+  tracer.run_to_completion(&mut computer)?;
+  print!("{}", tracer.summary(&computer.instructions, 5));
+  Ok(())
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::day_12::Register;
+
+  static LOOP: &str = "\
+    cpy 3 b\n\
+    inc a\n\
+    dec b\n\
+    jnz b -2\n\
+  ";
+
+  #[test]
+  fn counts_and_loops() {
+    let mut computer = Computer::new(LOOP);
+    let mut tracer = Tracer::new(&computer);
+    tracer.run_to_completion(&mut computer).unwrap();
+    assert_eq!(3, computer.read_register(Register::A));
+    assert_eq!(
+      vec![1, 3, 3, 3],
+      (0..4).map(|i| tracer.count(i)).collect::<Vec<_>>()
+    );
+    assert_eq!(vec![(1, 3), (2, 3)], tracer.hottest(2));
+    // The last jnz falls through, so the loop is only taken twice.
+    assert_eq!(vec![(3, 1, 2)], tracer.hot_loops());
+    assert_eq!(
+      "10 steps\n\
+       \x20       1            1  cpy 3 b\n\
+       \x20 #1    2            3  inc a\n\
+       \x20 #2    3            3  dec b\n\
+       \x20       4            3  jnz b -2\n\
+       Hot loops:\n\
+       \x20    4 -> 2    2 times\n",
+      tracer.summary(&computer.instructions, 2)
+    );
+  }
+
+  #[test]
+  fn trace_file() {
+    let path = crate::test_util::temp_path("day_12_trace.tsv");
+    let path = path.to_str().unwrap();
+    let mut computer = Computer::new(LOOP);
+    let mut tracer = Tracer::new(&computer).with_trace_file(path).unwrap();
+    tracer.run_to_completion(&mut computer).unwrap();
+    let trace = std::fs::read_to_string(path).unwrap();
+    std::fs::remove_file(path).unwrap();
+    let lines: Vec<&str> = trace.lines().collect();
+    assert_eq!(10, lines.len());
+    assert_eq!("1\t1\tcpy 3 b\t0\t3\t0\t0", lines[0]);
+    assert_eq!("10\t4\tjnz b -2\t3\t0\t0\t0", lines[9]);
+  }
+
+  #[test]
+  fn my_input_profile() {
+    let mut computer = Computer::new(include_str!("day_12_input.txt"));
+    let mut tracer = Tracer::new(&computer);
+    tracer.run_to_completion(&mut computer).unwrap();
+    assert_eq!(318020, computer.read_register(Register::A));
+    // The inner fibonacci loop dominates.
+    assert_eq!(
+      vec![11, 12, 13],
+      tracer
+        .hottest(3)
+        .into_iter()
+        .map(|(idx, _)| idx + 1)
+        .collect::<Vec<_>>()
+    );
+    let (from, to, _) = tracer.hot_loops()[0];
+    assert_eq!((12, 10), (from, to));
+  }
+}
@@ -1,32 +1,133 @@
 #![allow(dead_code)]
 
-use crate::astar::AStarSearcher;
+use crate::{
+  astar::AStarSearcher,
+  grid::{Grid, Point},
+  ppm::{Image, Rgb},
+  tsp::{shortest_tour, Distances, Tour, TourKind},
+};
 use colored::Colorize;
-use std::collections::{BTreeSet, VecDeque};
+use smallvec::SmallVec;
+use std::{
+  collections::{BTreeSet, VecDeque},
+  error::Error,
+};
 
-#[derive(Debug, Copy, Clone, PartialOrd, Ord, PartialEq, Eq)]
-struct Point(i64, i64);
-impl Point {
-  fn taxicab_distance(self, other: Self) -> u64 {
-    ((self.0 - other.0).abs() + (self.1 - other.1).abs()) as u64
+/// Where a maze's walls come from. Locations with negative coordinates are
+/// always walls.
+enum WallSource {
+  // The office building formula from the puzzle.
+  FavoriteNumber(i64),
+  // `true` is a wall. Everything outside of the map is a wall too.
+  Map(Grid<bool>),
+  Custom(Box<dyn Fn(Point) -> bool>),
+}
+impl WallSource {
+  fn is_wall(&self, point: Point) -> bool {
+    let Point { x, y } = point;
+    if x < 0 || y < 0 {
+      return true;
+    }
+    match self {
+      WallSource::FavoriteNumber(favorite_number) => {
+        (x * x + 3 * x + 2 * x * y + y + y * y + favorite_number).count_ones() % 2 == 1
+      }
+      WallSource::Map(map) => map.get(point).copied().unwrap_or(true),
+      WallSource::Custom(is_wall) => is_wall(point),
+    }
+  }
+}
+
+/// A precomputed bit per location of the top left `width` by `height` of a
+/// maze.
+struct WallCache {
+  width: i64,
+  height: i64,
+  bits: Vec<u64>,
+}
+impl WallCache {
+  fn new(source: &WallSource, width: i64, height: i64) -> Result<Self, Box<dyn Error>> {
+    let area = match width.checked_mul(height) {
+      Some(area) if width > 0 && height > 0 => area as usize,
+      _ => return Err(format!("Can't cache walls for a {}x{} area", width, height).into()),
+    };
+    let mut bits = vec![0; area.div_ceil(64)];
+    for y in 0..height {
+      for x in 0..width {
+        if source.is_wall(Point::new(x, y)) {
+          let idx = (y * width + x) as usize;
+          bits[idx / 64] |= 1 << (idx % 64);
+        }
+      }
+    }
+    Ok(Self {
+      width,
+      height,
+      bits,
+    })
+  }
+
+  fn get(&self, point: Point) -> Option<bool> {
+    if !point.within(self.width, self.height) {
+      return None;
+    }
+    let idx = (point.y * self.width + point.x) as usize;
+    Some(self.bits[idx / 64] & (1 << (idx % 64)) != 0)
   }
 }
 
-#[derive(Clone, Copy)]
 struct Maze {
-  favorite_number: i64,
+  walls: WallSource,
+  cache: Option<WallCache>,
 }
 impl Maze {
   fn new(favorite_number: i64) -> Self {
-    Self { favorite_number }
+    Self::with_walls(WallSource::FavoriteNumber(favorite_number))
+  }
+
+  fn with_walls(walls: WallSource) -> Self {
+    Self { walls, cache: None }
+  }
+
+  /// A maze drawn in ASCII, with `#` for walls and `.` for open space.
+  fn from_map(map: &str) -> Result<Self, Box<dyn Error>> {
+    Ok(Self::with_walls(WallSource::Map(map.parse()?)))
+  }
+
+  fn from_map_file(path: &str) -> Result<Self, Box<dyn Error>> {
+    Self::from_map(&std::fs::read_to_string(path)?)
+  }
+
+  fn from_fn(is_wall: impl Fn(Point) -> bool + 'static) -> Self {
+    Self::with_walls(WallSource::Custom(Box::new(is_wall)))
+  }
+
+  /// Precomputes the walls in the top left `width` by `height` of the maze,
+  /// for searches which look up the same locations many times.
+  fn cached(mut self, width: i64, height: i64) -> Result<Self, Box<dyn Error>> {
+    self.cache = Some(WallCache::new(&self.walls, width, height)?);
+    Ok(self)
   }
-  fn is_wall(&self, Point(x, y): Point) -> bool {
-    (x * x + 3 * x + 2 * x * y + y + y * y + self.favorite_number).count_ones() % 2 == 1
+
+  fn is_wall(&self, point: Point) -> bool {
+    match self.cache.as_ref().and_then(|cache| cache.get(point)) {
+      Some(wall) => wall,
+      None => self.walls.is_wall(point),
+    }
+  }
+
+  /// The top left `width` by `height` of the maze as a map.
+  fn to_grid(&self, width: i64, height: i64) -> Grid<bool> {
+    let mut grid = Grid::new(width as usize, height as usize, false);
+    for point in grid.points().collect::<Vec<_>>() {
+      grid[point] = self.is_wall(point);
+    }
+    grid
   }
 
   fn min_path_between(&self, from: Point, to: Point) -> (BTreeSet<Point>, Option<u64>) {
     let mut searcher = MazeSearcher {
-      maze: *self,
+      maze: self,
       target: to,
     }
     .caching();
@@ -34,7 +135,25 @@ impl Maze {
     return (searcher.seen, result.map(|v| v.0));
   }
 
+  /// The shortest route from the first of `points` which visits all of them,
+  /// only considering paths of at most `within` steps between each pair.
+  fn shortest_tour(&self, points: &[Point], within: u64, kind: TourKind) -> Option<Tour> {
+    shortest_tour(&Distances::new(points, within, |p| self.neighbors(p)), kind)
+  }
+
   fn count_locations_within_distance(&self, from: Point, within: u64) -> (BTreeSet<Point>, u64) {
+    self.count_locations_within_distance_observed(from, within, |_, _, _| {})
+  }
+
+  /// Like `count_locations_within_distance`, but calls `observer` with each
+  /// point as it's counted, the points visited so far and the queue of points
+  /// still to count.
+  fn count_locations_within_distance_observed(
+    &self,
+    from: Point,
+    within: u64,
+    mut observer: impl FnMut(Point, &BTreeSet<Point>, &VecDeque<(u64, Point)>),
+  ) -> (BTreeSet<Point>, u64) {
     let mut count: u64 = 0;
     let mut vec = VecDeque::new();
     let mut visited = std::collections::BTreeSet::new();
@@ -52,63 +171,123 @@ impl Maze {
         visited.insert(neighbor);
         vec.push_back((steps + 1, neighbor));
       }
+      observer(point, &visited, &vec);
     }
     (visited, count)
   }
 
-  fn neighbors(&self, Point(x, y): Point) -> Vec<Point> {
-    let mut result = Vec::with_capacity(4);
-    if x != 0 {
-      let point = Point(x - 1, y);
-      if !self.is_wall(point) {
-        result.push(point);
+  /// Draws the top left `width` by `height` of the maze, one pixel per
+  /// location, with `highlight` overriding the color of open locations.
+  fn draw(&self, width: i64, height: i64, highlight: impl Fn(Point) -> Option<Rgb>) -> Image {
+    let mut image = Image::new(width as usize, height as usize, OPEN);
+    for y in 0..height {
+      for x in 0..width {
+        let point = Point::new(x, y);
+        let color = if self.is_wall(point) {
+          WALL
+        } else {
+          highlight(point).unwrap_or(OPEN)
+        };
+        image.set(x as usize, y as usize, color);
       }
     }
-    if y != 0 {
-      let point = Point(x, y - 1);
-      if !self.is_wall(point) {
-        result.push(point);
+    image
+  }
+
+  fn search_frame(
+    &self,
+    (width, height): (i64, i64),
+    closed: &BTreeSet<Point>,
+    frontier: &BTreeSet<Point>,
+    current: Point,
+    endpoints: &[Point],
+  ) -> Image {
+    self.draw(width, height, |point| {
+      if endpoints.contains(&point) {
+        Some(ENDPOINT)
+      } else if point == current {
+        Some(CURRENT)
+      } else if frontier.contains(&point) {
+        Some(FRONTIER)
+      } else if closed.contains(&point) {
+        Some(CLOSED)
+      } else {
+        None
       }
+    })
+  }
+
+  /// One frame per location that A* expands while running
+  /// `min_path_between`, showing the closed set, the frontier, the location
+  /// being expanded and the endpoints.
+  fn min_path_frames(&self, from: Point, to: Point, size: (i64, i64)) -> (Vec<Image>, Option<u64>) {
+    let mut frames = Vec::new();
+    let mut closed = BTreeSet::new();
+    let mut searcher = MazeSearcher {
+      maze: self,
+      target: to,
     }
-    let point = Point(x + 1, y);
-    if !self.is_wall(point) {
-      result.push(point);
-    }
-    let point = Point(x, y + 1);
-    if !self.is_wall(point) {
-      result.push(point);
-    }
-    result
+    .caching();
+    let result = searcher.search_observed(from, |expansion| {
+      closed.insert(*expansion.node);
+      let frontier = expansion.frontier().copied().collect();
+      frames.push(self.search_frame(size, &closed, &frontier, *expansion.node, &[from, to]));
+    });
+    (frames, result.map(|v| v.0))
+  }
+
+  /// One frame per location counted by `count_locations_within_distance`,
+  /// showing the breadth first search spreading out from `from`.
+  fn within_distance_frames(&self, from: Point, within: u64, size: (i64, i64)) -> Vec<Image> {
+    let mut frames = Vec::new();
+    let mut closed = BTreeSet::new();
+    self.count_locations_within_distance_observed(from, within, |point, _, queue| {
+      closed.insert(point);
+      let frontier = queue.iter().map(|(_, p)| *p).collect();
+      frames.push(self.search_frame(size, &closed, &frontier, point, &[from]));
+    });
+    frames
+  }
+
+  fn neighbors(&self, point: Point) -> impl Iterator<Item = Point> + '_ {
+    IntoIterator::into_iter(point.neighbors()).filter(move |p| !self.is_wall(*p))
   }
 }
-struct MazeSearcher {
-  maze: Maze,
+struct MazeSearcher<'a> {
+  maze: &'a Maze,
   target: Point,
 }
-impl crate::astar::AStarSearcher for MazeSearcher {
+impl<'a> crate::astar::AStarSearcher for MazeSearcher<'a> {
   type Node = Point;
-  type Successors = Vec<Self::Node>;
+  type Successors = SmallVec<[Self::Node; 4]>;
 
   fn optimistic_distance(&self, node: &Self::Node) -> u64 {
     node.taxicab_distance(self.target)
   }
 
   fn successors(&mut self, node: &Self::Node) -> Self::Successors {
-    self.maze.neighbors(*node)
+    self.maze.neighbors(*node).collect()
   }
 }
 
-struct VisualizedMaze {
-  maze: Maze,
+const WALL: Rgb = [48, 48, 64];
+const OPEN: Rgb = [240, 240, 240];
+const CLOSED: Rgb = [110, 190, 110];
+const FRONTIER: Rgb = [250, 200, 60];
+const CURRENT: Rgb = [220, 50, 50];
+const ENDPOINT: Rgb = [60, 110, 220];
+
+struct VisualizedMaze<'a> {
+  maze: &'a Maze,
   visited: BTreeSet<Point>,
   height: i64,
   width: i64,
 }
-impl std::fmt::Display for VisualizedMaze {
+impl<'a> std::fmt::Display for VisualizedMaze<'a> {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     for y in 0..self.height {
       for x in 0..self.width {
-        let point = Point(x, y);
+        let point = Point::new(x, y);
         let wall = self.maze.is_wall(point);
         let visited = self.visited.contains(&point);
         if wall && visited {
@@ -127,6 +306,33 @@ impl std::fmt::Display for VisualizedMaze {
   }
 }
 
+impl<'a> VisualizedMaze<'a> {
+  fn to_image(&self) -> Image {
+    self.maze.draw(self.width, self.height, |point| {
+      if self.visited.contains(&point) {
+        Some(CLOSED)
+      } else {
+        None
+      }
+    })
+  }
+}
+
+/// Writes animations of both parts of the puzzle for the maze with the given
+/// favorite number to `dir`, as numbered PPM frames.
+pub fn export_search_animations(favorite_number: &str, dir: &str) -> Result<(), Box<dyn Error>> {
+  let (from, to) = (Point::new(1, 1), Point::new(31, 39));
+  let size = (to.x + 8, to.y + 8);
+  let maze = Maze::new(favorite_number.parse()?).cached(size.0, size.1)?;
+  let (frames, distance) = maze.min_path_frames(from, to, size);
+  crate::ppm::save_frames(&frames, dir, "min_path_", 4)?;
+  println!("{} frames of A*, distance {:?}", frames.len(), distance);
+  let frames = maze.within_distance_frames(from, 50, size);
+  crate::ppm::save_frames(&frames, dir, "within_50_", 4)?;
+  println!("{} frames of breadth first search", frames.len());
+  Ok(())
+}
+
 #[cfg(test)]
 mod test {
   use super::*;
@@ -134,11 +340,11 @@ mod test {
   #[test]
   fn examples() {
     let maze = Maze::new(10);
-    let (visited, dist) = maze.min_path_between(Point(1, 1), Point(7, 4));
+    let (visited, dist) = maze.min_path_between(Point::new(1, 1), Point::new(7, 4));
     println!(
       "\n{}",
       VisualizedMaze {
-        maze,
+        maze: &maze,
         visited,
         width: 10,
         height: 10
@@ -147,12 +353,146 @@ mod test {
     assert_eq!(Some(11), dist);
   }
 
+  #[test]
+  fn images() {
+    let maze = Maze::new(10);
+    let (visited, _) = maze.min_path_between(Point::new(1, 1), Point::new(7, 4));
+    let image = VisualizedMaze {
+      maze: &maze,
+      visited,
+      width: 10,
+      height: 10,
+    }
+    .to_image();
+    assert_eq!((10, 10), (image.width(), image.height()));
+    assert_eq!(WALL, image.get(1, 0));
+    assert_eq!(OPEN, image.get(9, 2));
+    assert_eq!(CLOSED, image.get(7, 4));
+
+    let (frames, dist) = maze.min_path_frames(Point::new(1, 1), Point::new(7, 4), (10, 10));
+    assert_eq!(Some(11), dist);
+    assert_eq!(ENDPOINT, frames[0].get(1, 1));
+    assert_eq!(FRONTIER, frames[0].get(1, 2));
+    assert_eq!(CURRENT, frames[1].get(1, 2));
+    assert_eq!(CLOSED, frames.last().unwrap().get(1, 2));
+
+    let frames = maze.within_distance_frames(Point::new(1, 1), 2, (10, 10));
+    assert_eq!(
+      maze.count_locations_within_distance(Point::new(1, 1), 2).1,
+      frames.len() as u64
+    );
+    let last = frames.last().unwrap();
+    assert!((0..10).all(|x| (0..10).all(|y| last.get(x, y) != FRONTIER)));
+  }
+
+  #[test]
+  fn wall_sources() {
+    let formula = Maze::new(10);
+    let map = formula.to_grid(10, 7).to_string();
+    assert_eq!(
+      ".#.####.##\n\
+       ..#..#...#\n\
+       #....##...\n\
+       ###.#.###.\n\
+       .##..#..#.\n\
+       ..##....#.\n\
+       #...##.###\n",
+      map
+    );
+    let from_map = Maze::from_map(&map).unwrap();
+    assert_eq!(
+      Some(11),
+      from_map
+        .min_path_between(Point::new(1, 1), Point::new(7, 4))
+        .1
+    );
+    // Everything off the edge of the map is a wall.
+    assert!(from_map.is_wall(Point::new(10, 2)));
+
+    let path = crate::test_util::temp_path("day_13_map.txt");
+    let path = path.to_str().unwrap();
+    std::fs::write(path, &map).unwrap();
+    let from_file = Maze::from_map_file(path).unwrap();
+    std::fs::remove_file(path).unwrap();
+    assert_eq!(
+      (5, 5),
+      (
+        from_map
+          .count_locations_within_distance(Point::new(1, 1), 2)
+          .1,
+        from_file
+          .count_locations_within_distance(Point::new(1, 1), 2)
+          .1
+      )
+    );
+
+    let open_plan = Maze::from_fn(|_| false);
+    assert_eq!(
+      Some(9),
+      open_plan
+        .min_path_between(Point::ORIGIN, Point::new(4, 5))
+        .1
+    );
+    assert!(open_plan.is_wall(Point::new(-1, 0)));
+  }
+
+  #[test]
+  fn tours() {
+    let maze = Maze::new(10);
+    let points = [Point::new(1, 1), Point::new(7, 4), Point::new(0, 0)];
+    assert_eq!(
+      Some(Tour {
+        length: 15,
+        order: vec![0, 2, 1]
+      }),
+      maze.shortest_tour(&points, 100, TourKind::Open)
+    );
+    assert_eq!(
+      Some(26),
+      maze
+        .shortest_tour(&points, 100, TourKind::Return)
+        .map(|t| t.length)
+    );
+    assert_eq!(None, maze.shortest_tour(&points, 5, TourKind::Open));
+
+    // Walled off points are unreachable, even though the maze goes on
+    // forever.
+    let divided = Maze::from_fn(|p| p.x == 5);
+    assert_eq!(
+      None,
+      divided.shortest_tour(&[Point::new(1, 1), Point::new(7, 0)], 100, TourKind::Open)
+    );
+  }
+
+  #[test]
+  fn cache() {
+    let maze = Maze::new(1358);
+    let cached = Maze::new(1358).cached(45, 45).unwrap();
+    for y in -1..50 {
+      for x in -1..50 {
+        let point = Point::new(x, y);
+        assert_eq!(maze.is_wall(point), cached.is_wall(point), "{:?}", point);
+      }
+    }
+    assert_eq!(
+      Some(96),
+      cached
+        .min_path_between(Point::new(1, 1), Point::new(31, 39))
+        .1
+    );
+    assert!(Maze::new(1358).cached(-1, 10).is_err());
+    assert!(Maze::new(1358).cached(10, 0).is_err());
+    assert!(Maze::new(1358).cached(i64::MAX, 2).is_err());
+  }
+
   #[test]
   fn my_input() {
     let maze = Maze::new(1358);
     assert_eq!(
       Some(96),
-      maze.min_path_between(Point(1, 1), Point(31, 39)).1
+      maze
+        .min_path_between(Point::new(1, 1), Point::new(31, 39))
+        .1
     );
   }
 
@@ -161,20 +501,27 @@ mod test {
   fn part_2_my_input() {
     let maze = Maze::new(1358);
     for i in 0..=50 {
-      let (visited, result) = maze.count_locations_within_distance(Point(1, 1), i);
+      let (visited, result) = maze.count_locations_within_distance(Point::new(1, 1), i);
       println!(
         "\nFound {} distinct points within {}:\n{}",
         result,
         i,
         VisualizedMaze {
-          maze,
+          maze: &maze,
           visited,
           width: 30,
           height: 30
         }
       );
     }
-    let (_visited, result) = maze.count_locations_within_distance(Point(1, 1), 50);
+    let (_visited, result) = maze.count_locations_within_distance(Point::new(1, 1), 50);
+    assert_eq!(141, result);
+  }
+
+  #[test]
+  fn part_2_cached() {
+    let maze = Maze::new(1358).cached(52, 52).unwrap();
+    let (_visited, result) = maze.count_locations_within_distance(Point::new(1, 1), 50);
     assert_eq!(141, result);
   }
 }
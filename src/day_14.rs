@@ -1,120 +1,208 @@
 #![allow(dead_code)]
 
-use itertools::Itertools;
 use md5::Digest;
 use smallvec::SmallVec;
-use std::fmt::Write;
-use std::{collections::VecDeque, iter::Enumerate};
+use std::{
+  collections::{BTreeMap, VecDeque},
+  error::Error,
+  fs::File,
+  io::{BufWriter, Write},
+  iter::Enumerate,
+  ops::Range,
+  path::Path,
+  sync::mpsc::{sync_channel, Receiver},
+};
 
-use crate::md5::Md5Iterator;
+use crate::md5::{stretch_batch, HashBytes, Md5Iterator, Nibbles, LANES};
+use rayon::prelude::*;
 
-struct OneTimePadMatcher<T>
+/// Which runs in a hash make it a candidate key.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum RunChoice {
+  // Only the first long enough run counts, as in the puzzle.
+  First,
+  // Any long enough run counts, so a key can be confirmed by any of them.
+  All,
+}
+
+/// A hash is a key if it has a run of `run` of the same hex digit, and one of
+/// the next `window` hashes has a run of `confirmation` of that digit.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct LookaheadRule {
+  run: usize,
+  confirmation: usize,
+  window: usize,
+  runs: RunChoice,
+}
+impl Default for LookaheadRule {
+  fn default() -> Self {
+    Self {
+      run: 3,
+      confirmation: 5,
+      window: 1000,
+      runs: RunChoice::First,
+    }
+  }
+}
+
+/// Finds the keys, as (index, hash), in a stream of hashes.
+struct LookaheadMatcher<T, H>
 where
-  T: Iterator<Item = Digest>,
+  T: Iterator<Item = H>,
 {
+  rule: LookaheadRule,
   iter: Enumerate<T>,
-  current: Option<(usize, Digest)>,
-  candidates: VecDeque<Candidate>,
+  exhausted: bool,
+  // How many hashes have been read from `iter`.
+  read: usize,
+  candidates: VecDeque<Candidate<H>>,
 }
-impl<T> OneTimePadMatcher<T>
+impl<T, H> LookaheadMatcher<T, H>
 where
-  T: Iterator<Item = Digest>,
+  T: Iterator<Item = H>,
+  H: HashBytes,
 {
   fn new(iter: T) -> Self {
+    Self::with_rule(iter, LookaheadRule::default())
+  }
+
+  fn with_rule(iter: T, rule: LookaheadRule) -> Self {
     Self {
+      rule,
       iter: iter.enumerate(),
-      current: None,
+      exhausted: false,
+      read: 0,
       candidates: VecDeque::new(),
     }
   }
 
-  fn internal_next(&mut self) -> (usize, Digest) {
-    if let Some(v) = self.current {
-      self.current = None;
-      return v;
+  fn read_next(&mut self) {
+    let (idx, hash) = match self.iter.next() {
+      Some(next) => next,
+      None => {
+        self.exhausted = true;
+        return;
+      }
+    };
+    self.read = idx + 1;
+    let LookaheadRule {
+      run,
+      confirmation,
+      window,
+      runs: choice,
+    } = self.rule;
+    let runs: SmallVec<[(u8, usize); 4]> = Nibbles::new(&hash)
+      .runs()
+      .filter(|(_, len)| *len >= run.min(confirmation))
+      .collect();
+    for (nibble, _) in runs.iter().filter(|(_, len)| *len >= confirmation) {
+      for candidate in self.candidates.iter_mut() {
+        if idx - candidate.idx <= window && candidate.nibbles.contains(nibble) {
+          candidate.confirmed = true;
+        }
+      }
+    }
+    let mut nibbles: SmallVec<[u8; 4]> = runs
+      .iter()
+      .filter(|(_, len)| *len >= run)
+      .map(|(nibble, _)| *nibble)
+      .collect();
+    if choice == RunChoice::First {
+      nibbles.truncate(1);
+    }
+    if !nibbles.is_empty() {
+      self.candidates.push_back(Candidate {
+        idx,
+        hash,
+        nibbles,
+        confirmed: false,
+      });
     }
-    self.iter.next().unwrap()
   }
 }
-impl<T> Iterator for OneTimePadMatcher<T>
+impl<T, H> Iterator for LookaheadMatcher<T, H>
 where
-  T: Iterator<Item = Digest>,
+  T: Iterator<Item = H>,
+  H: HashBytes,
 {
-  type Item = (usize, Digest);
+  type Item = (usize, H);
 
   fn next(&mut self) -> Option<Self::Item> {
     loop {
-      let (idx, digest) = self.internal_next();
-      let runs = crate::md5::HexIterator::new(digest)
-        .group_by(|k| *k)
-        .into_iter()
-        .map(|(k, g)| (k, g.count()))
-        .filter(|(_, run_length)| *run_length >= 3)
-        .collect::<SmallVec<[(u8, usize); 4]>>();
-      if runs.is_empty() {
-        continue;
-      }
-
-      let cutoff = idx.saturating_sub(1000);
-      for (char, run_length) in runs.iter() {
-        if *run_length >= 5 {
-          for candidate in self.candidates.iter_mut() {
-            if candidate.idx < cutoff {
-              continue;
-            }
-            if candidate.running_char == *char {
-              candidate.longer_match_found = true;
-            }
+      // The oldest candidate is decided once it's confirmed, or once its
+      // window has passed with no confirmation.
+      if let Some(oldest) = self.candidates.front() {
+        let window_passed = self.exhausted || self.read > oldest.idx + self.rule.window;
+        if oldest.confirmed || window_passed {
+          let oldest = self.candidates.pop_front().unwrap();
+          if oldest.confirmed {
+            return Some((oldest.idx, oldest.hash));
           }
+          continue;
         }
+      } else if self.exhausted {
+        return None;
       }
-      let first_run_of_three = runs
-        .into_iter()
-        .filter(|(_c, usize)| *usize >= 3)
-        .map(|(c, _usize)| c)
-        .next();
-      if let Some(run_of_three) = first_run_of_three {
-        self.candidates.push_back(Candidate {
-          idx,
-          digest,
-          running_char: run_of_three,
-          longer_match_found: false,
-        });
-      }
-
-      while let Some(candidate) = self.candidates.get(0) {
-        if candidate.idx < cutoff {
-          let longer_match_found = candidate.longer_match_found;
-          let result = (candidate.idx, candidate.digest);
-          self.candidates.pop_front();
-          if longer_match_found {
-            return Some(result);
-          }
-        } else {
-          break;
-        }
-      }
+      self.read_next();
     }
   }
 }
 
-struct Candidate {
+struct Candidate<H> {
   idx: usize,
-  running_char: u8,
-  digest: Digest,
-  longer_match_found: bool,
+  hash: H,
+  nibbles: SmallVec<[u8; 4]>,
+  confirmed: bool,
+}
+
+/// Rehashes the hex of `digest` `rounds` times.
+fn stretch(mut digest: Digest, rounds: usize) -> Digest {
+  for _ in 0..rounds {
+    digest = crate::md5::compute(crate::md5::to_hex(&digest));
+  }
+  digest
+}
+
+/// Stretches each of `digests`, running `LANES` chains side by side.
+fn stretch_all(digests: &mut [Digest], rounds: usize) {
+  let mut chunks = digests.chunks_exact_mut(LANES);
+  for chunk in &mut chunks {
+    let mut lanes: [Digest; LANES] = std::array::from_fn(|l| chunk[l]);
+    stretch_batch(&mut lanes, rounds);
+    chunk.copy_from_slice(&lanes);
+  }
+  for digest in chunks.into_remainder() {
+    *digest = stretch(*digest, rounds);
+  }
+}
+
+/// The stretched digests for the indexes in `range`, computed in parallel.
+fn stretched_range(salt: &str, rounds: usize, range: Range<usize>) -> Vec<Digest> {
+  let tasks = (range.end - range.start).div_ceil(LANES);
+  (0..tasks)
+    .into_par_iter()
+    .flat_map_iter(|task| {
+      let start = range.start + task * LANES;
+      let end = (start + LANES).min(range.end);
+      let mut digests: Vec<Digest> = Md5Iterator::starting_at(salt, start as u64)
+        .take(end - start)
+        .collect();
+      stretch_all(&mut digests, rounds);
+      digests
+    })
+    .collect()
 }
 
 struct StretchedHashIter {
   md5_iter: Md5Iterator,
-  scratch_string: String,
+  rounds: usize,
 }
 
 impl StretchedHashIter {
-  fn new(seed: &str) -> Self {
+  fn new(seed: &str, rounds: usize) -> Self {
     Self {
       md5_iter: Md5Iterator::new(seed),
-      scratch_string: String::with_capacity(32),
+      rounds,
     }
   }
 }
@@ -123,16 +211,205 @@ impl Iterator for StretchedHashIter {
   type Item = Digest;
 
   fn next(&mut self) -> Option<Self::Item> {
-    let mut digest = self.md5_iter.next()?;
-    for _ in 0..2016 {
-      self.scratch_string.clear();
-      write!(self.scratch_string, "{:x}", digest).ok()?;
-      digest = md5::compute(&self.scratch_string);
+    Some(stretch(self.md5_iter.next()?, self.rounds))
+  }
+}
+
+/// Stretched digests for indexes 0, 1, 2, etc, computed a block at a time on
+/// a background thread, so the next block is being worked on while the
+/// matcher looks through the current one.
+struct ParallelStretchedHashIter {
+  blocks: Receiver<Vec<Digest>>,
+  current: std::vec::IntoIter<Digest>,
+}
+impl ParallelStretchedHashIter {
+  fn new(salt: &str, rounds: usize) -> Self {
+    Self::with_block_size(salt, rounds, 1024)
+  }
+
+  fn with_block_size(salt: &str, rounds: usize, block_size: usize) -> Self {
+    // Enough to stay ahead of the matcher, without running off computing
+    // hashes no one wants.
+    let (sender, blocks) = sync_channel(2);
+    let salt = salt.to_string();
+    std::thread::spawn(move || {
+      let mut start = 0;
+      loop {
+        let block = stretched_range(&salt, rounds, start..start + block_size);
+        if sender.send(block).is_err() {
+          // The iterator was dropped.
+          return;
+        }
+        start += block_size;
+      }
+    });
+    Self {
+      blocks,
+      current: Vec::new().into_iter(),
+    }
+  }
+}
+impl Iterator for ParallelStretchedHashIter {
+  type Item = Digest;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    loop {
+      if let Some(digest) = self.current.next() {
+        return Some(digest);
+      }
+      self.current = self.blocks.recv().ok()?.into_iter();
+    }
+  }
+}
+
+/// Remembers stretched digests by salt, stretch rounds and index, so they
+/// only need computing once, even across runs if saved to a file.
+#[derive(Default)]
+struct StretchedHashCache {
+  // The digests for indexes 0, 1, 2, etc, for each (salt, rounds).
+  digests: BTreeMap<(String, usize), Vec<Digest>>,
+}
+impl StretchedHashCache {
+  fn new() -> Self {
+    Self::default()
+  }
+
+  fn get(&mut self, salt: &str, rounds: usize, idx: usize) -> Digest {
+    self.fill(salt, rounds, idx + 1)[idx]
+  }
+
+  /// Makes sure the first `len` digests for `salt` and `rounds` are stored,
+  /// computing any missing ones in parallel.
+  fn fill(&mut self, salt: &str, rounds: usize, len: usize) -> &[Digest] {
+    let digests = self.digests.entry((salt.to_string(), rounds)).or_default();
+    if len > digests.len() {
+      digests.extend(stretched_range(salt, rounds, digests.len()..len));
+    }
+    digests
+  }
+
+  /// How many digests are stored for `salt` and `rounds`.
+  fn len(&self, salt: &str, rounds: usize) -> usize {
+    self
+      .digests
+      .get(&(salt.to_string(), rounds))
+      .map_or(0, |d| d.len())
+  }
+
+  fn iter<'a>(&'a mut self, salt: &'a str, rounds: usize) -> CachedStretchedHashes<'a> {
+    CachedStretchedHashes {
+      digests: self.digests.entry((salt.to_string(), rounds)).or_default(),
+      salt,
+      rounds,
+      idx: 0,
+    }
+  }
+
+  /// Saves as a line of `salt rounds count` for each salt and rounds,
+  /// followed by that many hex digests, one per line.
+  fn save(&self, path: &str) -> std::io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    for ((salt, rounds), digests) in self.digests.iter() {
+      writeln!(out, "{} {} {}", salt, rounds, digests.len())?;
+      for digest in digests {
+        writeln!(out, "{:x}", digest)?;
+      }
+    }
+    out.flush()
+  }
+
+  fn load(path: &str) -> Result<Self, Box<dyn Error>> {
+    let file = std::fs::read_to_string(path)?;
+    let mut lines = file.lines();
+    let mut cache = Self::new();
+    while let Some(header) = lines.next() {
+      // The salt comes first so it can contain spaces itself.
+      let (salt, rounds, count) = match header.rsplitn(3, ' ').collect::<Vec<_>>()[..] {
+        [count, rounds, salt] => (salt, rounds.parse()?, count.parse()?),
+        _ => return Err(format!("Bad stretched hash cache header: {}", header).into()),
+      };
+      let mut digests = Vec::with_capacity(count);
+      for _ in 0..count {
+        let hex = lines.next().ok_or("Stretched hash cache is truncated")?;
+        digests.push(parse_digest(hex)?);
+      }
+      cache.digests.insert((salt.to_string(), rounds), digests);
+    }
+    Ok(cache)
+  }
+
+  /// Loads the cache at `path`, or starts an empty one if there isn't one.
+  fn load_or_new(path: &str) -> Result<Self, Box<dyn Error>> {
+    if Path::new(path).exists() {
+      Self::load(path)
+    } else {
+      Ok(Self::new())
+    }
+  }
+}
+
+fn parse_digest(hex: &str) -> Result<Digest, Box<dyn Error>> {
+  if hex.len() != 32 || !hex.is_ascii() {
+    return Err(format!("Bad digest: {}", hex).into());
+  }
+  let mut digest = [0; 16];
+  for (i, byte) in digest.iter_mut().enumerate() {
+    *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16)?;
+  }
+  Ok(Digest(digest))
+}
+
+/// Stretched digests for indexes 0, 1, 2, etc, from a cache.
+struct CachedStretchedHashes<'a> {
+  digests: &'a mut Vec<Digest>,
+  salt: &'a str,
+  rounds: usize,
+  idx: usize,
+}
+impl<'a> Iterator for CachedStretchedHashes<'a> {
+  type Item = Digest;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.idx >= self.digests.len() {
+      // Fill ahead, so there's a block of work to do in parallel.
+      let missing = self.digests.len()..self.idx + 1024;
+      self
+        .digests
+        .extend(stretched_range(self.salt, self.rounds, missing));
     }
+    let digest = self.digests[self.idx];
+    self.idx += 1;
     Some(digest)
   }
 }
 
+/// The index of the 64th key for `salt`, with each hash stretched `rounds`
+/// times. With a `cache_path`, stretched hashes are loaded from and saved
+/// back to that file, so running again only computes hashes it hasn't seen.
+fn sixty_fourth_key(
+  salt: &str,
+  rounds: usize,
+  cache_path: Option<&str>,
+) -> Result<usize, Box<dyn Error>> {
+  let key = match cache_path {
+    Some(cache_path) => {
+      let mut cache = StretchedHashCache::load_or_new(cache_path)?;
+      let key = LookaheadMatcher::new(cache.iter(salt, rounds)).nth(63);
+      cache.save(cache_path)?;
+      key
+    }
+    None => LookaheadMatcher::new(ParallelStretchedHashIter::new(salt, rounds)).nth(63),
+  };
+  Ok(key.ok_or("Stretching hashes failed")?.0)
+}
+
+/// Prints the answer to part 2 for `salt`, caching stretched hashes in
+/// `cache_path` if given.
+pub fn print_part_2(salt: &str, cache_path: Option<&str>) -> Result<(), Box<dyn Error>> {
+  println!("{}", sixty_fourth_key(salt, 2016, cache_path)?);
+  Ok(())
+}
+
 #[cfg(test)]
 mod test {
   use super::*;
@@ -145,7 +422,7 @@ mod test {
       .collect();
     assert_eq!(hexes, format!("{:x}", digest));
     assert_eq!("0034e0923cc38887a57bd7b1d4f953df", hexes);
-    let mut matcher = OneTimePadMatcher::new(Md5Iterator::new("abc"));
+    let mut matcher = LookaheadMatcher::new(Md5Iterator::new("abc"));
     assert_eq!(
       Some((
         39,
@@ -162,7 +439,7 @@ mod test {
   #[cfg(not(debug_assertions))]
   #[test]
   fn examples() {
-    let matcher = OneTimePadMatcher::new(Md5Iterator::new("abc"));
+    let matcher = LookaheadMatcher::new(Md5Iterator::new("abc"));
     let expected_sixty_fourth_idx = 22728;
     assert_eq!(
       Some(63),
@@ -174,7 +451,7 @@ mod test {
     );
     assert_eq!(
       Some(expected_sixty_fourth_idx),
-      OneTimePadMatcher::new(Md5Iterator::new("abc"))
+      LookaheadMatcher::new(Md5Iterator::new("abc"))
         .skip(63)
         .next()
         .map(|(oidx, _)| oidx)
@@ -187,19 +464,183 @@ mod test {
   fn my_input() {
     assert_eq!(
       Some(15035),
-      OneTimePadMatcher::new(Md5Iterator::new(MY_INPUT))
+      LookaheadMatcher::new(Md5Iterator::new(MY_INPUT))
         .skip(63)
         .next()
         .map(|(oidx, _)| oidx)
     );
   }
 
+  #[test]
+  fn lookahead_rules() {
+    let hashes = vec![
+      vec![0xaa, 0xab, 0xbb],
+      vec![0x12, 0x34],
+      vec![0x11, 0x10],
+      vec![0xbb, 0xbb],
+      vec![0x11, 0x11],
+    ];
+    let rule = LookaheadRule {
+      run: 3,
+      confirmation: 4,
+      window: 3,
+      runs: RunChoice::First,
+    };
+    let keys = |rule| {
+      LookaheadMatcher::with_rule(hashes.clone().into_iter(), rule)
+        .map(|(idx, _)| idx)
+        .collect::<Vec<_>>()
+    };
+    // The last hash runs out of hashes to confirm it.
+    assert_eq!(vec![2], keys(rule));
+    assert_eq!(
+      vec![0, 2],
+      keys(LookaheadRule {
+        runs: RunChoice::All,
+        ..rule
+      })
+    );
+    assert_eq!(
+      vec![2],
+      keys(LookaheadRule {
+        runs: RunChoice::All,
+        window: 2,
+        ..rule
+      })
+    );
+    assert_eq!(Vec::<usize>::new(), keys(LookaheadRule::default()));
+  }
+
+  #[test]
+  fn other_hash_functions() {
+    use crate::{md5::HashIterator, sha::Sha1};
+    let hashes: Vec<[u8; 20]> = HashIterator::<Sha1>::new("abc").take(3000).collect();
+    let (idx, key) = LookaheadMatcher::new(hashes.iter().copied())
+      .next()
+      .unwrap();
+    assert_eq!(hashes[idx], key);
+    let (digit, _) = Nibbles::new(&key)
+      .runs()
+      .find(|(_, len)| *len >= 3)
+      .unwrap();
+    assert!(hashes[idx + 1..=idx + 1000]
+      .iter()
+      .any(|hash| Nibbles::new(hash)
+        .runs()
+        .any(|run| run.0 == digit && run.1 >= 5)));
+  }
+
+  #[test]
+  fn finite_streams() {
+    let keys: Vec<usize> = LookaheadMatcher::new(Md5Iterator::new("abc").take(1100))
+      .map(|(idx, _)| idx)
+      .collect();
+    assert_eq!(vec![39, 92, 110, 184, 314, 459, 461, 771], keys);
+  }
+
+  #[test]
+  fn stretching() {
+    assert_eq!(
+      "a107ff634856bb300138cac6568c0f24",
+      format!("{:x}", stretch(crate::md5::compute("abc0"), 2016))
+    );
+    let mut cache = StretchedHashCache::new();
+    assert_eq!(
+      StretchedHashIter::new("abc", 3)
+        .take(40)
+        .collect::<Vec<_>>(),
+      cache.iter("abc", 3).take(40).collect::<Vec<_>>()
+    );
+    assert_eq!(
+      stretch(crate::md5::compute("abc17"), 5),
+      cache.get("abc", 5, 17)
+    );
+    assert_eq!(
+      (1024, 18, 0),
+      (
+        cache.len("abc", 3),
+        cache.len("abc", 5),
+        cache.len("xyz", 3)
+      )
+    );
+  }
+
+  #[test]
+  fn cache_file() {
+    let path = crate::test_util::temp_path("day_14_cache.txt");
+    let path = path.to_str().unwrap();
+    let _ = std::fs::remove_file(path);
+    let mut cache = StretchedHashCache::load_or_new(path).unwrap();
+    let first_match = LookaheadMatcher::new(cache.iter("abc", 1)).next();
+    cache.get("xyz", 2, 3);
+    cache.save(path).unwrap();
+
+    let mut loaded = StretchedHashCache::load_or_new(path).unwrap();
+    std::fs::remove_file(path).unwrap();
+    assert_eq!(cache.len("abc", 1), loaded.len("abc", 1));
+    assert_eq!(4, loaded.len("xyz", 2));
+    assert_eq!(
+      first_match,
+      LookaheadMatcher::new(loaded.iter("abc", 1)).next()
+    );
+    assert_eq!(cache.digests, loaded.digests);
+  }
+
+  #[test]
+  fn awkward_cache_files() {
+    let path = crate::test_util::temp_path("day_14_awkward_cache.txt");
+    let path = path.to_str().unwrap();
+    let mut cache = StretchedHashCache::new();
+    cache.get("a salt with spaces ", 2, 1);
+    cache.get("", 1, 0);
+    cache.save(path).unwrap();
+    let loaded = StretchedHashCache::load(path).unwrap();
+    assert_eq!(cache.digests, loaded.digests);
+    assert_eq!(2, loaded.len("a salt with spaces ", 2));
+
+    // Thirty two bytes, but not thirty two hex digits.
+    std::fs::write(path, format!("abc 1 1\n{}é\n", "0".repeat(30))).unwrap();
+    assert!(StretchedHashCache::load(path).is_err());
+    std::fs::write(path, "abc 1\n").unwrap();
+    assert!(StretchedHashCache::load(path).is_err());
+    std::fs::remove_file(path).unwrap();
+  }
+
+  #[test]
+  fn cached_solving() {
+    let path = crate::test_util::temp_path("day_14_solve_cache.txt");
+    let path = path.to_str().unwrap();
+    let _ = std::fs::remove_file(path);
+    let expected = sixty_fourth_key("abc", 2, None).unwrap();
+    assert_eq!(expected, sixty_fourth_key("abc", 2, Some(path)).unwrap());
+    let saved = StretchedHashCache::load(path).unwrap();
+    assert!(saved.len("abc", 2) > expected + 1000);
+
+    // A second run reads what it needs from the file.
+    assert_eq!(expected, sixty_fourth_key("abc", 2, Some(path)).unwrap());
+    let resaved = StretchedHashCache::load(path).unwrap();
+    std::fs::remove_file(path).unwrap();
+    assert_eq!(saved.digests, resaved.digests);
+  }
+
+  #[test]
+  fn parallel_stretching() {
+    let sequential: Vec<Digest> = StretchedHashIter::new("abc", 4).take(100).collect();
+    assert_eq!(
+      sequential,
+      ParallelStretchedHashIter::with_block_size("abc", 4, 30)
+        .take(100)
+        .collect::<Vec<_>>()
+    );
+    assert_eq!(sequential[5..50].to_vec(), stretched_range("abc", 4, 5..50));
+  }
+
   #[cfg(not(debug_assertions))]
   #[test]
   fn part_2_example() {
     assert_eq!(
       Some(22551),
-      OneTimePadMatcher::new(StretchedHashIter::new("abc"))
+      LookaheadMatcher::new(ParallelStretchedHashIter::new("abc", 2016))
         .skip(63)
         .next()
         .map(|(oidx, _)| oidx)
@@ -209,12 +650,6 @@ mod test {
   #[cfg(not(debug_assertions))]
   #[test]
   fn part_2_my_input() {
-    assert_eq!(
-      Some(19968),
-      OneTimePadMatcher::new(StretchedHashIter::new(MY_INPUT))
-        .skip(63)
-        .next()
-        .map(|(oidx, _)| oidx)
-    );
+    assert_eq!(19968, sixty_fourth_key(MY_INPUT, 2016, None).unwrap());
   }
 }
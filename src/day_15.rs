@@ -1,5 +1,7 @@
 #![allow(dead_code)]
 
+use crate::number_theory::{chinese_remainder, Congruence};
+
 #[derive(Copy, Clone)]
 struct Disc {
   size: u64,
@@ -15,7 +17,22 @@ impl Disc {
   }
 
   fn position_in(&self, elapsed: u64) -> u64 {
-    (self.position + elapsed) % self.size
+    (self.position + elapsed % self.size) % self.size
+  }
+}
+
+/// The times the ball can be dropped: the first one, then every `period`
+/// seconds after that, when the discs are all back where they were.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct DropTimes {
+  first: u64,
+  period: u64,
+}
+impl DropTimes {
+  /// The drop times in order, stopping at the last one that fits in a `u64`.
+  fn iter(&self) -> impl Iterator<Item = u64> {
+    let DropTimes { first, period } = *self;
+    (0..=u64::MAX).map_while(move |n| n.checked_mul(period)?.checked_add(first))
   }
 }
 
@@ -45,6 +62,54 @@ impl Sculpture {
     }
   }
 
+  /// Every time from now that the ball can be dropped, or `None` if the
+  /// slots never line up or the first time doesn't fit in a `u64`.
+  fn drop_times(&self) -> Option<DropTimes> {
+    // The ball reaches disc i after i + 1 seconds, so dropping it after t
+    // seconds needs position + t + i + 1 ≡ 0 modulo the disc's size.
+    let alignment = chinese_remainder(
+      self
+        .discs
+        .iter()
+        .enumerate()
+        .map(|(i, d)| Congruence::new(-(d.position as i128 + i as i128 + 1), d.size)),
+    )?;
+    Some(DropTimes {
+      first: self.time.checked_add(alignment.residue)?,
+      period: alignment.modulus,
+    })
+  }
+
+  /// The earliest time from now that the ball can be dropped, or `None` if
+  /// the slots never line up.
+  fn earliest_drop_time(&self) -> Option<u64> {
+    self.drop_times().map(|times| times.first)
+  }
+
+  /// The next `k` times the ball can be dropped.
+  fn next_drop_times(&self, k: usize) -> impl Iterator<Item = u64> {
+    self
+      .drop_times()
+      .into_iter()
+      .flat_map(|times| times.iter())
+      .take(k)
+  }
+
+  /// The indexes of the discs the ball would bounce off if it were dropped at
+  /// `time`, or `None` if that time has already passed.
+  fn blocking_discs(&self, time: u64) -> Option<Vec<usize>> {
+    let elapsed = time.checked_sub(self.time)?;
+    Some(
+      self
+        .discs
+        .iter()
+        .enumerate()
+        .filter(|(i, d)| d.position_in(elapsed % d.size + *i as u64 + 1) != 0)
+        .map(|(i, _)| i)
+        .collect(),
+    )
+  }
+
   fn ball_can_drop(&self) -> bool {
     for (i, d) in self.discs.iter().enumerate() {
       let position = d.position_in(i as u64 + 1);
@@ -82,6 +147,101 @@ mod test {
     assert_eq!(5, sculpture.first_drop_time());
   }
 
+  #[test]
+  fn remainder_theorem() {
+    let sculpture = Sculpture::new(EXAMPLE.to_vec());
+    assert_eq!(Some(5), sculpture.earliest_drop_time());
+    let mut discs = MY_INPUT.to_vec();
+    assert_eq!(
+      Some(400589),
+      Sculpture::new(discs.clone()).earliest_drop_time()
+    );
+    discs.push(Disc::new(11, 0));
+    let mut sculpture = Sculpture::new(discs);
+    assert_eq!(Some(3045959), sculpture.earliest_drop_time());
+    sculpture.wait(10);
+    assert_eq!(Some(3045959), sculpture.earliest_drop_time());
+
+    // The first disc only lines up at odd times, and the second at even ones.
+    let never = Sculpture::new(vec![Disc::new(2, 0), Disc::new(4, 0)]);
+    assert_eq!(None, never.earliest_drop_time());
+    // Sizes which share factors can still line up.
+    let mut shared = Sculpture::new(vec![Disc::new(4, 1), Disc::new(6, 2)]);
+    let time = shared.earliest_drop_time().unwrap();
+    assert_eq!(time, shared.first_drop_time());
+  }
+
+  #[test]
+  fn every_drop_time() {
+    let sculpture = Sculpture::new(EXAMPLE.to_vec());
+    assert_eq!(
+      Some(DropTimes {
+        first: 5,
+        period: 10
+      }),
+      sculpture.drop_times()
+    );
+    assert_eq!(
+      vec![5, 15, 25],
+      sculpture.next_drop_times(3).collect::<Vec<_>>()
+    );
+    assert_eq!(
+      Some(17 * 3 * 19 * 13 * 7 * 5),
+      Sculpture::new(MY_INPUT.to_vec())
+        .drop_times()
+        .map(|t| t.period)
+    );
+    // Sizes sharing factors repeat sooner than their product.
+    let shared = Sculpture::new(vec![Disc::new(4, 1), Disc::new(6, 2)]);
+    assert_eq!(
+      vec![2, 14, 26],
+      shared.next_drop_times(3).collect::<Vec<_>>()
+    );
+    let never = Sculpture::new(vec![Disc::new(2, 0), Disc::new(4, 0)]);
+    assert_eq!(0, never.next_drop_times(3).count());
+    // Huge positions and start times don't overflow.
+    let far = Sculpture::new(vec![Disc::new(5, u64::MAX), Disc::new(2, u64::MAX)]);
+    assert_eq!(
+      Some(DropTimes {
+        first: 9,
+        period: 10
+      }),
+      far.drop_times()
+    );
+    let mut late = Sculpture::new(EXAMPLE.to_vec());
+    late.time = u64::MAX - 2;
+    assert_eq!(None, late.drop_times());
+    // The times stop rather than overflow.
+    let huge = DropTimes {
+      first: u64::MAX - 10,
+      period: 4,
+    };
+    assert_eq!(
+      vec![u64::MAX - 10, u64::MAX - 6, u64::MAX - 2],
+      huge.iter().take(10).collect::<Vec<_>>()
+    );
+  }
+
+  #[test]
+  fn blocking() {
+    let mut sculpture = Sculpture::new(EXAMPLE.to_vec());
+    // At time 0 the first disc is at 0 + 1 + 4 = 0 (mod 5), but the second
+    // is at 0 + 2 + 1 = 1 (mod 2).
+    assert_eq!(Some(vec![1]), sculpture.blocking_discs(0));
+    assert_eq!(Some(vec![0]), sculpture.blocking_discs(1));
+    assert_eq!(Some(vec![]), sculpture.blocking_discs(5));
+    sculpture.wait(3);
+    assert_eq!(None, sculpture.blocking_discs(2));
+    assert_eq!(Some(vec![]), sculpture.blocking_discs(15));
+    assert_eq!(Some(vec![0, 1]), sculpture.blocking_discs(6));
+    let sculpture = Sculpture::new(MY_INPUT.to_vec());
+    for time in sculpture.next_drop_times(5) {
+      assert_eq!(Some(vec![]), sculpture.blocking_discs(time));
+    }
+    // Times far in the future don't overflow.
+    assert!(sculpture.blocking_discs(u64::MAX).is_some());
+  }
+
   #[cfg(not(debug_assertions))]
   #[test]
   fn my_input() {
@@ -1,53 +1,137 @@
 #![allow(dead_code)]
 
+/// The dragon curve data, packed 64 bits to a word with bit `i` at bit
+/// `i % 64` of word `i / 64`. Bits past the end of the last word are always
+/// zero.
 struct DragonGenerator {
-  vec: Vec<bool>,
+  words: Vec<u64>,
+  len: usize,
 }
 impl DragonGenerator {
   fn new(init: &str) -> DragonGenerator {
-    Self {
-      vec: init
-        .chars()
-        .map(|c| match c {
-          '0' => false,
-          '1' => true,
-          _ => panic!("hm"),
-        })
-        .collect(),
+    let mut gen = Self {
+      words: Vec::new(),
+      len: 0,
+    };
+    for c in init.chars() {
+      let bit = match c {
+        '0' => false,
+        '1' => true,
+        _ => panic!("hm"),
+      };
+      gen.push_bits(bit as u64, 1);
     }
+    gen
+  }
+
+  fn len(&self) -> usize {
+    self.len
+  }
+
+  fn bit(&self, i: usize) -> bool {
+    (self.words[i / 64] >> (i % 64)) & 1 == 1
   }
+
+  fn bits(&self) -> impl Iterator<Item = bool> + '_ {
+    (0..self.len).map(move |i| self.bit(i))
+  }
+
+  /// Appends the lowest `count` bits of `bits`, which must be zero above
+  /// that.
+  fn push_bits(&mut self, bits: u64, count: usize) {
+    if count == 0 {
+      return;
+    }
+    let offset = self.len % 64;
+    if offset == 0 {
+      self.words.push(bits);
+    } else {
+      *self.words.last_mut().unwrap() |= bits << offset;
+      if offset + count > 64 {
+        self.words.push(bits >> (64 - offset));
+      }
+    }
+    self.len += count;
+  }
+
+  /// Zeros the bits past the end, after the length has been cut down.
+  fn clear_tail(&mut self) {
+    self.words.truncate(self.len.div_ceil(64));
+    if self.len & 63 != 0 {
+      *self.words.last_mut().unwrap() &= (1 << (self.len % 64)) - 1;
+    }
+  }
+
   fn extend(&mut self) {
-    let mut tail = self.vec.iter().rev().map(|b| !b).collect::<Vec<_>>();
-    self.vec.push(false);
-    self.vec.append(&mut tail);
+    let len = self.len;
+    // Reversing every word reverses all of them padded out to a whole
+    // number of words, so the reversed bits start `padding` bits in.
+    let padding = (64 - len % 64) % 64;
+    let reversed: Vec<u64> = self.words.iter().rev().map(|w| w.reverse_bits()).collect();
+    self.push_bits(0, 1);
+    let mut remaining = len;
+    for (i, word) in reversed.iter().enumerate() {
+      if remaining == 0 {
+        break;
+      }
+      let mut tail = word >> padding;
+      if padding != 0 {
+        tail |= reversed.get(i + 1).map_or(0, |next| next << (64 - padding));
+      }
+      let count = remaining.min(64);
+      let mask = if count == 64 { !0 } else { (1 << count) - 1 };
+      self.push_bits(!tail & mask, count);
+      remaining -= count;
+    }
   }
+
   fn extend_up_to(&mut self, desired_len: usize) {
-    while self.vec.len() < desired_len {
+    while self.len < desired_len {
       self.extend();
     }
-    self.vec.truncate(desired_len);
+    self.len = desired_len;
+    self.clear_tail();
   }
+
   fn checksum(&mut self) {
     self.checksum_round();
-    while self.vec.len() % 2 == 0 {
+    while self.len & 1 == 0 {
       self.checksum_round();
     }
   }
+
   fn checksum_round(&mut self) {
-    let new_len = self.vec.len() / 2;
-    for i in 0..new_len {
-      self.vec[i] = match (self.vec[i * 2], self.vec[(i * 2) + 1]) {
-        (false, false) | (true, true) => true,
-        _ => false,
-      };
+    // Each pair of bits becomes 1 if they're the same, and each word of
+    // pairs packs down into half a word.
+    for i in 0..self.words.len() {
+      let word = self.words[i];
+      let same = !(word ^ (word >> 1));
+      let half = pack_even_bits(same);
+      if i & 1 == 0 {
+        self.words[i / 2] = half;
+      } else {
+        self.words[i / 2] |= half << 32;
+      }
     }
-    self.vec.truncate(new_len);
+    self.len /= 2;
+    self.clear_tail();
   }
 }
+
+/// The even numbered bits of `word`, packed into the low 32 bits.
+fn pack_even_bits(word: u64) -> u64 {
+  let mut x = word & 0x5555_5555_5555_5555;
+  x = (x | (x >> 1)) & 0x3333_3333_3333_3333;
+  x = (x | (x >> 2)) & 0x0f0f_0f0f_0f0f_0f0f;
+  x = (x | (x >> 4)) & 0x00ff_00ff_00ff_00ff;
+  x = (x | (x >> 8)) & 0x0000_ffff_0000_ffff;
+  (x | (x >> 16)) & 0x0000_0000_ffff_ffff
+}
+
 impl std::fmt::Display for DragonGenerator {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    for b in self.vec.iter() {
-      if *b {
+    for b in self.bits() {
+      if b {
         f.write_str("1")?;
       } else {
         f.write_str("0")?;
@@ -57,6 +141,113 @@ impl std::fmt::Display for DragonGenerator {
   }
 }
 
+/// The dragon curve data from a seed, worked out a bit at a time rather than
+/// built up in memory.
+///
+/// After enough steps the data is the seed `a` and its reversed complement
+/// `b` alternating, `a ? b ? a ? b ...`, with the separator bits between them
+/// following the regular paperfolding sequence.
+struct DragonSequence {
+  seed: Vec<bool>,
+  // ones_in_seed[i] is the number of ones in the first i bits of the seed.
+  ones_in_seed: Vec<u64>,
+}
+impl DragonSequence {
+  fn new(init: &str) -> Self {
+    let seed: Vec<bool> = DragonGenerator::new(init).bits().collect();
+    let mut ones_in_seed = vec![0];
+    for bit in seed.iter() {
+      ones_in_seed.push(ones_in_seed.last().unwrap() + *bit as u64);
+    }
+    Self { seed, ones_in_seed }
+  }
+
+  fn seed_len(&self) -> u64 {
+    self.seed.len() as u64
+  }
+
+  /// The bit at index `i` of the data.
+  fn bit_at(&self, i: u64) -> bool {
+    let len = self.seed_len();
+    let (block, offset) = (i / (len + 1), i % (len + 1));
+    if offset == len {
+      return separator(block + 1);
+    }
+    if block & 1 == 0 {
+      self.seed[offset as usize]
+    } else {
+      !self.seed[(len - 1 - offset) as usize]
+    }
+  }
+
+  /// The number of ones in the first `i` bits of the data.
+  fn ones_before(&self, i: u64) -> u64 {
+    let len = self.seed_len();
+    let seed_ones = self.ones_in_seed[self.seed.len()];
+    let (blocks, offset) = (i / (len + 1), i % (len + 1));
+    // Whole blocks alternate between the seed and its reversed complement.
+    let mut ones = blocks.div_ceil(2) * seed_ones + blocks / 2 * (len - seed_ones);
+    ones += separator_ones(blocks);
+    // Then part of the next block, and maybe the separator after it.
+    let partial = offset.min(len);
+    ones += if blocks & 1 == 0 {
+      self.ones_in_seed[partial as usize]
+    } else {
+      // The first bits of the reversed complement are the last bits of the
+      // seed, flipped.
+      partial - (seed_ones - self.ones_in_seed[(len - partial) as usize])
+    };
+    ones
+  }
+
+  fn iter(&self) -> impl Iterator<Item = bool> + '_ {
+    (0..).map(move |i| self.bit_at(i))
+  }
+
+  /// The checksum of the first `disk_len` bits, without generating them.
+  ///
+  /// Each checksum round pairs bits up, so after all of them every digit
+  /// comes from a chunk of the data whose size is a power of two. The pairing
+  /// keeps track of parity, so a digit is 1 when its chunk has an even
+  /// number of ones.
+  fn checksum(&self, disk_len: u64) -> String {
+    // There's always at least one round, which drops an odd last bit.
+    let after_first_round = disk_len / 2;
+    if after_first_round == 0 {
+      return String::new();
+    }
+    let rounds = after_first_round.trailing_zeros() + 1;
+    let chunk = 1 << rounds;
+    (0..disk_len >> rounds)
+      .map(|c| {
+        let ones = self.ones_before((c + 1) * chunk) - self.ones_before(c * chunk);
+        if ones & 1 == 0 {
+          '1'
+        } else {
+          '0'
+        }
+      })
+      .collect()
+  }
+}
+
+/// The `n`th separator, counting from 1, of the regular paperfolding
+/// sequence: 0 0 1 0 0 1 1 ...
+fn separator(n: u64) -> bool {
+  (n >> (n.trailing_zeros() + 1)) & 1 == 1
+}
+
+/// The number of ones among the first `n` separators.
+fn separator_ones(n: u64) -> u64 {
+  // The odd numbered separators go 0 1 0 1 ..., and the even numbered ones
+  // repeat the whole sequence, so separator 2m is separator m.
+  if n == 0 {
+    0
+  } else {
+    n.div_ceil(2) / 2 + separator_ones(n / 2)
+  }
+}
+
 #[cfg(test)]
 mod test {
   use super::*;
@@ -83,6 +274,30 @@ mod test {
     assert_eq!("01100", format!("{}", gen));
   }
 
+  #[test]
+  fn packed_words() {
+    // Long enough that the reversed halves straddle word boundaries.
+    for seed in ["1", "10000", "01111010110010011", &"10".repeat(40)].iter() {
+      let mut gen = DragonGenerator::new(seed);
+      let mut reference: Vec<bool> = seed.chars().map(|c| c == '1').collect();
+      for _ in 0..6 {
+        gen.extend();
+        let mut tail: Vec<bool> = reference.iter().rev().map(|b| !b).collect();
+        reference.push(false);
+        reference.append(&mut tail);
+        assert_eq!(reference, gen.bits().collect::<Vec<_>>(), "{}", seed);
+      }
+      gen.extend_up_to(1000);
+      reference.truncate(1000);
+      while reference.len() & 1 == 0 {
+        reference = reference.chunks(2).map(|pair| pair[0] == pair[1]).collect();
+        gen.checksum_round();
+        assert_eq!(reference, gen.bits().collect::<Vec<_>>(), "{}", seed);
+      }
+    }
+    assert_eq!(0x0000_0000_0000_ff0f, pack_even_bits(0x5555_0055));
+  }
+
   #[test]
   fn my_input() {
     let mut gen = DragonGenerator::new("01111010110010011");
@@ -91,6 +306,52 @@ mod test {
     assert_eq!("00100111000101111", format!("{}", gen));
   }
 
+  #[test]
+  fn sequence() {
+    for seed in ["1", "0", "10000", "01111010110010011", "1101"].iter() {
+      let mut gen = DragonGenerator::new(seed);
+      gen.extend_up_to(1000);
+      let sequence = DragonSequence::new(seed);
+      let streamed: String = sequence
+        .iter()
+        .take(1000)
+        .map(|b| if b { '1' } else { '0' })
+        .collect();
+      assert_eq!(gen.to_string(), streamed, "{}", seed);
+      for i in 0..=1000 {
+        let ones = gen.bits().take(i).filter(|b| *b).count() as u64;
+        assert_eq!(ones, sequence.ones_before(i as u64), "{} {}", seed, i);
+      }
+    }
+    assert_eq!(
+      vec![false, false, true, false, false, true, true],
+      (1..=7).map(separator).collect::<Vec<_>>()
+    );
+  }
+
+  #[test]
+  fn streamed_checksums() {
+    assert_eq!("01100", DragonSequence::new("10000").checksum(20));
+    let seed = "01111010110010011";
+    for len in [272, 100, 96, 50, 35, 7, 2].iter() {
+      let mut gen = DragonGenerator::new(seed);
+      gen.extend_up_to(*len);
+      gen.checksum();
+      assert_eq!(
+        gen.to_string(),
+        DragonSequence::new(seed).checksum(*len as u64),
+        "{}",
+        len
+      );
+    }
+    assert_eq!(
+      "11101110011100110",
+      DragonSequence::new(seed).checksum(35651584)
+    );
+    // Far more bits than would fit in memory.
+    assert_eq!(17, DragonSequence::new(seed).checksum(17 << 40).len());
+  }
+
   #[cfg(not(debug_assertions))]
   #[test]
   fn part_2_my_input() {
@@ -1,170 +1,154 @@
 #![allow(dead_code)]
 
 use smallvec::SmallVec;
-use Direction::*;
+use std::{cmp::Ordering, fmt, marker::PhantomData};
 
-use crate::{astar::AStarSearcher, md5::HexIterator};
+use crate::{
+  astar::AStarSearcher,
+  grid::{Direction, Point},
+  md5::{HashFunction, HexIterator, Md5},
+};
 use rayon::iter::IntoParallelRefIterator;
 use rayon::iter::ParallelIterator;
 
-#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
-enum Direction {
-  Up,
-  Down,
-  Left,
-  Right,
-}
-impl Direction {
-  fn as_char(&self) -> char {
-    match self {
-      Up => 'U',
-      Down => 'D',
-      Left => 'L',
-      Right => 'R',
-    }
-  }
-}
+// The vault is a 4x4 grid of rooms, entered at the top left.
+const SIZE: i64 = 4;
+const VAULT: Point = Point { x: 3, y: 3 };
 
-#[derive(PartialEq, Eq, PartialOrd, Ord, Debug)]
-struct MoveList {
+/// A path through the vault, along with a hasher which has already seen the
+/// passcode and the moves so far. Extending the path only hashes the new
+/// move, rather than the whole passcode and path again.
+#[derive(Clone)]
+struct MoveList<H: HashFunction = Md5> {
   moves: SmallVec<[Direction; 16]>,
+  hasher: H,
 }
 
-impl MoveList {
-  fn with_move(&self, direction: Direction) -> MoveList {
+impl<H: HashFunction> MoveList<H> {
+  fn new(passcode: &str) -> Self {
+    let mut hasher = H::default();
+    hasher.update(passcode.as_bytes());
+    Self {
+      moves: Default::default(),
+      hasher,
+    }
+  }
+
+  fn with_move(&self, direction: Direction) -> Self {
     let mut moves = self.moves.clone();
     moves.push(direction);
-    MoveList { moves }
-  }
-
-  fn location(&self) -> (i64, i64) {
-    self.moves.iter().fold((0, 0), |(x, y), d| match d {
-      Up => (x, y - 1),
-      Down => (x, y + 1),
-      Left => (x - 1, y),
-      Right => (x + 1, y),
-    })
-  }
-
-  fn neighbors(&self, options: SmallVec<[Direction; 4]>) -> Vec<MoveList> {
-    let (x, y) = self.location();
-    let mut results = Vec::with_capacity(4);
-    for direction in options.into_iter() {
-      match direction {
-        Up => {
-          if y > 0 {
-            results.push(self.with_move(direction));
-          }
-        }
-        Down => {
-          if y < 3 {
-            results.push(self.with_move(direction));
-          }
-        }
-        Left => {
-          if x > 0 {
-            results.push(self.with_move(direction));
-          }
-        }
-        Right => {
-          if x < 3 {
-            results.push(self.with_move(direction));
-          }
-        }
-      }
-    }
-    results
+    let mut hasher = self.hasher.clone();
+    hasher.update(&[direction.as_char() as u8]);
+    MoveList { moves, hasher }
   }
-}
 
-struct Vault {
-  passcode_len: usize,
-  scratch_str: String,
-}
+  fn location(&self) -> Point {
+    self
+      .moves
+      .iter()
+      .fold(Point::ORIGIN, |point, d| point.step(*d))
+  }
 
-impl Vault {
-  fn new(passcode: &str) -> Self {
-    Self {
-      passcode_len: passcode.len(),
-      scratch_str: passcode.to_string(),
-    }
+  fn path(&self) -> String {
+    self.moves.iter().map(|d| d.as_char()).collect()
   }
 
-  fn find_doors(&mut self, moves: &MoveList) -> SmallVec<[MoveList; 4]> {
-    let (x, y) = moves.location();
-    if x == 3 && y == 3 {
+  /// The moves to rooms next door through open doors.
+  fn find_doors(&self) -> SmallVec<[Self; 4]> {
+    let location = self.location();
+    if location == VAULT {
       return Default::default();
     }
-    self.scratch_str.truncate(self.passcode_len);
-    for direction in moves.moves.iter() {
-      self.scratch_str.push(direction.as_char());
-    }
-    let digest = md5::compute(&self.scratch_str);
-    let mut hexes = HexIterator::new(digest);
+    let digest = self.hasher.clone().finalize();
     let mut results = SmallVec::new();
-
-    if hexes.next().unwrap() >= 0xb && y > 0 {
-      results.push(moves.with_move(Up));
-    }
-    if hexes.next().unwrap() >= 0xb && y < 3 {
-      results.push(moves.with_move(Down));
-    }
-    if hexes.next().unwrap() >= 0xb && x > 0 {
-      results.push(moves.with_move(Left));
-    }
-    if hexes.next().unwrap() >= 0xb && x < 3 {
-      results.push(moves.with_move(Right));
+    // The first four hex digits say whether the up, down, left and right
+    // doors are open.
+    for (direction, hex) in Direction::ALL.iter().zip(HexIterator::new(digest)) {
+      if hex >= 0xb && location.step(*direction).within(SIZE, SIZE) {
+        results.push(self.with_move(*direction));
+      }
     }
     results
   }
 }
 
-impl AStarSearcher for Vault {
-  type Node = MoveList;
+// Paths are compared by their moves alone, since the hasher state follows
+// from them.
+impl<H: HashFunction> PartialEq for MoveList<H> {
+  fn eq(&self, other: &Self) -> bool {
+    self.moves == other.moves
+  }
+}
+impl<H: HashFunction> Eq for MoveList<H> {}
+impl<H: HashFunction> PartialOrd for MoveList<H> {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+impl<H: HashFunction> Ord for MoveList<H> {
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.moves.cmp(&other.moves)
+  }
+}
+impl<H: HashFunction> fmt::Debug for MoveList<H> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("MoveList")
+      .field("moves", &self.moves)
+      .finish()
+  }
+}
+
+struct Vault<H: HashFunction = Md5> {
+  hash: PhantomData<H>,
+}
+
+impl<H: HashFunction> Vault<H> {
+  fn new() -> Self {
+    Self { hash: PhantomData }
+  }
+}
+
+impl<H: HashFunction> AStarSearcher for Vault<H> {
+  type Node = MoveList<H>;
   type Successors = SmallVec<[Self::Node; 4]>;
 
   fn optimistic_distance(&self, node: &Self::Node) -> u64 {
-    let (x, y) = node.location();
-    ((3 - x).abs() as u64) + ((3 - y).abs() as u64)
+    node.location().taxicab_distance(VAULT)
   }
 
   fn successors(&mut self, node: &Self::Node) -> Self::Successors {
-    self.find_doors(&node)
+    node.find_doors()
   }
 }
 
 fn problem(passcode: &str) -> String {
-  let mut vault = Vault::new(passcode);
-  let (_len, moves) = vault
-    .search(MoveList {
-      moves: Default::default(),
-    })
-    .unwrap();
-  moves.moves.into_iter().map(|d| d.as_char()).collect()
+  problem_with::<Md5>(passcode).unwrap()
+}
+
+/// The shortest path to the vault when the doors are decided by the hash
+/// function `H`, if there is one.
+fn problem_with<H: HashFunction>(passcode: &str) -> Option<String> {
+  let (_len, moves) = Vault::<H>::new().search(MoveList::new(passcode))?;
+  Some(moves.path())
 }
 
-fn part_2_par(passcode: &str, node: &MoveList) -> usize {
-  let mut vault = Vault::new(passcode);
-  if node.location() == (3, 3) {
+fn part_2_par<H: HashFunction + Send + Sync>(node: &MoveList<H>) -> usize {
+  if node.location() == VAULT {
     return node.moves.len();
   }
-  let mut solution_len: usize = 0;
-  let best_child = vault
-    .find_doors(node)
+  node
+    .find_doors()
     .par_iter()
-    .map(|node| part_2_par(passcode, node))
-    .reduce(|| 0, |left, right| left.max(right));
-  solution_len = solution_len.max(best_child);
-  solution_len
+    .map(part_2_par)
+    .reduce(|| 0, |left, right| left.max(right))
 }
 
 fn problem_part_2(passcode: &str) -> usize {
-  part_2_par(
-    passcode,
-    &MoveList {
-      moves: Default::default(),
-    },
-  )
+  problem_part_2_with::<Md5>(passcode)
+}
+
+fn problem_part_2_with<H: HashFunction + Send + Sync>(passcode: &str) -> usize {
+  part_2_par::<H>(&MoveList::new(passcode))
 }
 
 #[cfg(test)]
@@ -183,6 +167,37 @@ mod test {
     assert_eq!("RLRDRDUDDR", problem("rrrbmfta"));
   }
 
+  #[test]
+  fn hashing_carries_on_from_the_parent() {
+    let moves = MoveList::<Md5>::new("hijkl")
+      .with_move(Direction::Down)
+      .with_move(Direction::Right);
+    assert_eq!("DR", moves.path());
+    assert_eq!(
+      crate::md5::compute("hijklDR"),
+      moves.hasher.clone().finalize()
+    );
+    let doors = MoveList::<Md5>::new("hijkl").find_doors();
+    assert_eq!(
+      vec!["D"],
+      doors.iter().map(|m| m.path()).collect::<Vec<_>>()
+    );
+  }
+
+  #[test]
+  fn other_hash_functions() {
+    use crate::sha::Sha256;
+    assert_eq!(None, problem_with::<Sha256>("ihgpwlah"));
+    let path = problem_with::<Sha256>("kglvqrro").unwrap();
+    assert_eq!("RDULDDDRRURD", path);
+    let end = path
+      .chars()
+      .map(|c| Direction::from_char(c).unwrap())
+      .fold(Point::ORIGIN, |point, d| point.step(d));
+    assert_eq!(VAULT, end);
+    assert!(path.len() <= problem_part_2_with::<Sha256>("kglvqrro"));
+  }
+
   #[cfg(not(debug_assertions))]
   #[test]
   fn examples_part_2() {
@@ -1,156 +1,288 @@
 #![allow(dead_code)]
 
-use crate::md5::Md5Iterator;
-use md5::Digest;
-use rayon::prelude::*;
-use smallvec::SmallVec;
-
-struct DigestChunks {
-  idx: usize,
-  chunk_size: usize,
-}
-impl DigestChunks {
-  fn new() -> Self {
-    Self {
-      idx: 0,
-      chunk_size: 1024 * 1024,
-    }
-  }
+use colored::Colorize;
+use std::{
+  error::Error,
+  io::{self, Write},
+  time::{Instant, SystemTime, UNIX_EPOCH},
+};
 
-  fn next_chunk<'a>(
-    &mut self,
-    door_id: &'a str,
-  ) -> impl rayon::iter::ParallelIterator<Item = Digest> + 'a {
-    let range = self.idx..self.idx + self.chunk_size;
-    self.idx += self.chunk_size;
-    range
-      .into_par_iter()
-      .map(move |idx| md5::compute(format!("{}{}", door_id, idx)))
-      .filter(|digest| digest[0..2] == [0, 0] && digest[2] & 0xf0 == 0)
-  }
-}
+use crate::md5::{hex_digit, HashFunction, HashIterator, Md5, Miner, Nibbles, Predicate};
+
+// How many zeros a hash has to start with for its digits to be part of the
+// password.
+const DIFFICULTY: usize = 5;
 
-fn get_matched_par<'a>(door_id: &'a str) -> impl rayon::iter::ParallelIterator<Item = Digest> + 'a {
-  (0..usize::MAX)
-    .into_par_iter()
-    .map(move |idx| md5::compute(format!("{}{}", door_id, idx)))
-    .filter(|digest| digest[0..2] == [0, 0] && digest[2] & 0xf0 == 0)
+fn miner<H: HashFunction>(door_id: &str, difficulty: usize) -> Miner<H> {
+  Miner::new(door_id, Predicate::LeadingZeroNibbles(difficulty))
 }
 
-fn get_matched(door_id: &str) -> impl Iterator<Item = md5::Digest> {
-  Md5Iterator::new(door_id).filter(|digest| digest[0..2] == [0, 0] && digest[2] & 0xf0 == 0)
+/// The same matches as `miner`, as (index, digest), found one at a time.
+fn get_matched<H: HashFunction>(
+  door_id: &str,
+  difficulty: usize,
+) -> impl Iterator<Item = (u64, H::Digest)> {
+  (0..)
+    .zip(HashIterator::<H>::new(door_id))
+    .filter(move |(_, digest)| Nibbles::new(digest).leading_zero_nibbles() >= difficulty)
 }
 
-fn hex(u4: u8) -> char {
-  match u4 {
-    0 => '0',
-    1 => '1',
-    2 => '2',
-    3 => '3',
-    4 => '4',
-    5 => '5',
-    6 => '6',
-    7 => '7',
-    8 => '8',
-    9 => '9',
-    10 => 'a',
-    11 => 'b',
-    12 => 'c',
-    13 => 'd',
-    14 => 'e',
-    15 => 'f',
-    _ => panic!("Got unexpected value in hex(): {:?}", u4),
-  }
+fn compute_password(door_id: &str) -> Result<String, Box<dyn Error>> {
+  compute_password_with::<Md5>(door_id)
 }
 
-fn compute_password(door_id: &str) -> String {
-  let mut chunks = DigestChunks::new();
-  let mut result = String::with_capacity(8);
-  while result.len() < 8 {
-    result.push_str(
-      &chunks
-        .next_chunk(door_id)
-        .map(|digest| hex(digest[2] & 0x0f))
-        .collect::<String>(),
-    )
-  }
-  result.truncate(8);
-  result
+fn compute_password_with<H: HashFunction>(door_id: &str) -> Result<String, Box<dyn Error>> {
+  Ok(
+    miner::<H>(door_id, DIFFICULTY)
+      .first(8)?
+      .into_iter()
+      .map(|(_, digest)| hex_digit(Nibbles::new(&digest).nth_nibble(5)))
+      .collect(),
+  )
 }
 
-struct CustomSmallVec<A: smallvec::Array> {
-  inner: SmallVec<A>,
+fn compute_complex_password(door_id: &str) -> Result<String, Box<dyn Error>> {
+  compute_complex_password_with::<Md5>(door_id)
 }
-impl<A: smallvec::Array> FromParallelIterator<A::Item> for CustomSmallVec<A>
-where
-  A::Item: Send,
-{
-  fn from_par_iter<I>(par_iter: I) -> Self
-  where
-    I: IntoParallelIterator<Item = A::Item>,
-  {
-    Self {
-      inner: par_iter
-        .into_par_iter()
-        .fold(
-          || SmallVec::new(),
-          |mut vec, c| {
-            vec.push(c);
-            vec
-          },
-        )
-        .reduce(
-          || SmallVec::new(),
-          |mut l, mut r| {
-            l.append(&mut r);
-            l
-          },
-        ),
-    }
-  }
+
+fn compute_complex_password_with<H: HashFunction>(door_id: &str) -> Result<String, Box<dyn Error>> {
+  crack_complex_password(&miner::<H>(door_id, DIFFICULTY), |_| Ok(()))
 }
 
-fn compute_complex_password(door_id: &str) -> String {
+/// Fills in the password from the miner's matches, calling `progress` with
+/// the slots found so far after each chunk is searched. Gives up as soon as
+/// `progress` fails, or if the miner's range runs out first.
+fn crack_complex_password<H: HashFunction>(
+  miner: &Miner<H>,
+  mut progress: impl FnMut(&[Option<char>; 8]) -> Result<(), Box<dyn Error>>,
+) -> Result<String, Box<dyn Error>> {
   let mut result = [None; 8];
   let result_len = result.len();
-  let mut chunks = DigestChunks::new();
+  let mut chunks = miner.chunks()?;
   while result.iter().any(|v| v.is_none()) {
-    let CustomSmallVec {
-      inner: postition_chars,
-    }: CustomSmallVec<[(usize, char); 3]> = chunks
-      .next_chunk(door_id)
-      .map(|digest| {
-        (
-          (digest[2] & 0x0f) as usize,
-          format!("{:x}", (digest[3] & 0xf0)).chars().next().unwrap(),
-        )
-      })
-      .filter(|(position, _char)| *position < result_len)
-      .collect();
-    println!(
-      "found {} position_chars in chunk of size {}",
-      postition_chars.len(),
-      chunks.chunk_size
-    );
-    for (position, s) in postition_chars {
-      if let Some(_) = result[position] {
-        continue;
+    let chunk = chunks.next().ok_or_else(|| {
+      let unfilled = result.iter().filter(|v| v.is_none()).count();
+      format!("Range exhausted with {} slots unfilled", unfilled)
+    })?;
+    for (_, digest) in chunk {
+      let nibbles = Nibbles::new(&digest);
+      let position = nibbles.nth_nibble(5) as usize;
+      if position < result_len && result[position].is_none() {
+        result[position] = Some(hex_digit(nibbles.nth_nibble(6)));
       }
-      result[position] = Some(s);
     }
+    progress(&result)?;
+  }
+  Ok(result.iter().map(|v| v.unwrap()).collect())
+}
+
+/// A tiny xorshift generator, for flickering digits rather than anything
+/// which needs to be properly random.
+struct XorShift(u64);
+impl XorShift {
+  fn new(seed: u64) -> Self {
+    // Xorshift gets stuck at zero.
+    Self(seed.max(1))
   }
-  result.iter().map(|v| v.unwrap()).collect()
+
+  fn from_time() -> Self {
+    let now = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map(|d| d.as_nanos() as u64)
+      .unwrap_or(0);
+    Self::new(now)
+  }
+
+  fn next(&mut self) -> u64 {
+    self.0 ^= self.0 << 13;
+    self.0 ^= self.0 >> 7;
+    self.0 ^= self.0 << 17;
+    self.0
+  }
+
+  fn hex_digit(&mut self) -> char {
+    hex_digit((self.next() & 0x0f) as u8)
+  }
+}
+
+/// The password as it should be shown, with random digits in the slots
+/// which haven't been found yet.
+fn displayed_slots(slots: &[Option<char>; 8], rng: &mut XorShift) -> [(char, bool); 8] {
+  let mut displayed = [(' ', false); 8];
+  for (shown, slot) in displayed.iter_mut().zip(slots.iter()) {
+    *shown = match slot {
+      Some(c) => (*c, true),
+      None => (rng.hex_digit(), false),
+    };
+  }
+  displayed
+}
+
+// How many indexes to search between redraws.
+const DISPLAY_CHUNK_SIZE: u64 = 64 * 1024;
+
+/// Cracks the second password like a hacker in a movie, redrawing it in
+/// place as the slots are found.
+pub fn crack_cinematically(door_id: &str) -> Result<(), Box<dyn Error>> {
+  let miner = miner::<Md5>(door_id, DIFFICULTY).chunk_size(DISPLAY_CHUNK_SIZE);
+  let mut rng = XorShift::from_time();
+  let start = Instant::now();
+  let mut hashed = 0;
+  let mut stdout = io::stdout();
+  let password = crack_complex_password(&miner, |slots| {
+    hashed += DISPLAY_CHUNK_SIZE;
+    let rate = hashed as f64 / start.elapsed().as_secs_f64().max(1e-9) / 1e6;
+    let mut line = String::from("\r");
+    for (c, found) in displayed_slots(slots, &mut rng).iter() {
+      let c = c.to_string();
+      if *found {
+        line += &c.green().bold().to_string();
+      } else {
+        line += &c.dimmed().to_string();
+      }
+    }
+    line += &format!("  {:.2} MH/s", rate);
+    write!(stdout, "{}", line)?;
+    stdout.flush()?;
+    Ok(())
+  })?;
+  println!(
+    "\r{}  {} hashes in {:.1}s",
+    password.green().bold(),
+    hashed,
+    start.elapsed().as_secs_f64()
+  );
+  Ok(())
 }
 
 #[cfg(test)]
 mod test {
-  #[cfg(not(debug_assertions))]
   use super::*;
 
+  #[test]
+  fn flickering_digits() {
+    let mut rng = XorShift::new(42);
+    let mut again = XorShift::new(42);
+    assert_eq!(rng.next(), again.next());
+    assert_ne!(0, XorShift::new(0).next());
+
+    let slots = [None, Some('3'), None, None, Some('a'), None, None, None];
+    let displayed = displayed_slots(&slots, &mut rng);
+    assert_eq!(('3', true), displayed[1]);
+    assert_eq!(('a', true), displayed[4]);
+    assert!(displayed
+      .iter()
+      .all(|(c, _)| c.is_ascii_hexdigit() && !c.is_ascii_uppercase()));
+    assert_eq!(2, displayed.iter().filter(|(_, found)| *found).count());
+  }
+
+  #[test]
+  fn parallel_matches_sequential() {
+    for (door_id, difficulty) in [("abc", 2), ("reyedfim", 3), ("", 3)].iter() {
+      let expected: Vec<_> = get_matched::<Md5>(door_id, *difficulty).take(40).collect();
+      // Small chunks and tasks split across several threads, so matches from
+      // different threads have to be put back in order.
+      let miner = miner::<Md5>(door_id, *difficulty)
+        .threads(4)
+        .chunk_size(5_000);
+      assert_eq!(
+        expected,
+        miner.first(40).unwrap(),
+        "{} {}",
+        door_id,
+        difficulty
+      );
+      let chunked: Vec<_> = miner.chunks().unwrap().flatten().take(40).collect();
+      assert_eq!(expected, chunked, "{} {}", door_id, difficulty);
+    }
+  }
+
+  #[test]
+  fn parallel_matches_sequential_for_random_prefixes() {
+    let mut rng = XorShift::new(2016);
+    let range = 0..300;
+    for _ in 0..8 {
+      let len = rng.next() % 8;
+      let door_id: String = (0..len)
+        .map(|_| (b'a' + (rng.next() % 26) as u8) as char)
+        .collect();
+      for difficulty in 0..=2 {
+        let expected: Vec<_> = get_matched::<Md5>(&door_id, difficulty)
+          .take_while(|(idx, _)| range.contains(idx))
+          .collect();
+        for chunk_size in [0, 1, 7, 1000].iter() {
+          let miner = miner::<Md5>(&door_id, difficulty)
+            .range(range.clone())
+            .threads(3)
+            .chunk_size(*chunk_size);
+          let found: Vec<_> = miner.chunks().unwrap().flatten().collect();
+          assert_eq!(
+            expected, found,
+            "{:?} {} {}",
+            door_id, difficulty, chunk_size
+          );
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn failing_progress() {
+    let miner = Miner::<Md5>::new("abc", Predicate::LeadingZeroNibbles(3)).chunk_size(100);
+    let mut calls = 0;
+    let result = crack_complex_password(&miner, |_| {
+      calls += 1;
+      Err("the terminal went away".into())
+    });
+    assert_eq!("the terminal went away", result.unwrap_err().to_string());
+    assert_eq!(1, calls);
+  }
+
+  #[test]
+  fn exhausted_range() {
+    let miner = Miner::<Md5>::new("abc", Predicate::LeadingZeroNibbles(3))
+      .range(0..1000)
+      .chunk_size(100);
+    let mut slots = [None; 8];
+    let result = crack_complex_password(&miner, |s| {
+      slots = *s;
+      Ok(())
+    });
+    let unfilled = slots.iter().filter(|v| v.is_none()).count();
+    assert!(unfilled > 0);
+    assert_eq!(
+      format!("Range exhausted with {} slots unfilled", unfilled),
+      result.unwrap_err().to_string()
+    );
+  }
+
+  #[test]
+  fn progress() {
+    let miner = Miner::<Md5>::new("abc", Predicate::LeadingZeroNibbles(3)).chunk_size(10_000);
+    let mut seen = vec![];
+    let password = crack_complex_password(&miner, |slots| {
+      seen.push(*slots);
+      Ok(())
+    })
+    .unwrap();
+    assert!(seen
+      .windows(2)
+      .all(|w| (0..8).all(|i| w[0][i].is_none() || w[0][i] == w[1][i])));
+    assert_eq!(
+      password,
+      seen
+        .last()
+        .unwrap()
+        .iter()
+        .map(|c| c.unwrap())
+        .collect::<String>()
+    );
+  }
+
   #[cfg(not(debug_assertions))]
   #[test]
   fn example() {
-    assert_eq!("18f47a30", &compute_password("abc"));
+    assert_eq!("18f47a30", &compute_password("abc").unwrap());
   }
 
   const MY_INPUT: &'static str = "reyedfim";
@@ -158,18 +290,30 @@ mod test {
   #[cfg(not(debug_assertions))]
   #[test]
   fn my_input() {
-    assert_eq!("f97c354d", &compute_password(MY_INPUT));
+    assert_eq!("f97c354d", &compute_password(MY_INPUT).unwrap());
   }
 
   #[cfg(not(debug_assertions))]
   #[test]
   fn part_2_example() {
-    assert_eq!("05ace8e3", &compute_complex_password("abc"))
+    assert_eq!("05ace8e3", &compute_complex_password("abc").unwrap())
   }
 
   #[cfg(not(debug_assertions))]
   #[test]
   fn part_2_my_input() {
-    assert_eq!("863dde27", &compute_complex_password(MY_INPUT))
+    assert_eq!("863dde27", &compute_complex_password(MY_INPUT).unwrap())
+  }
+
+  #[test]
+  fn other_hash_functions() {
+    use crate::sha::Sha1;
+    // An easier difficulty, so it's quick enough to run unoptimized.
+    let sequential: Vec<_> = get_matched::<Sha1>("abc", 3).take(8).collect();
+    assert_eq!(8, sequential.len());
+    assert!(sequential
+      .iter()
+      .all(|(_, digest)| Nibbles::new(digest).leading_zero_nibbles() >= 3));
+    assert_eq!(sequential, miner::<Sha1>("abc", 3).first(8).unwrap());
   }
 }
@@ -1,21 +1,22 @@
 #![allow(dead_code)]
 
+use crate::grid::{Grid, Point};
 use regex::Regex;
 use std::fmt::Display;
 
 struct LittleScreen {
-  pixels: Vec<Vec<bool>>,
+  pixels: Grid<bool>,
 }
 impl LittleScreen {
   fn example_sized() -> Self {
     Self {
-      pixels: vec![vec![false; 7]; 3],
+      pixels: Grid::new(7, 3, false),
     }
   }
 
   fn full_sized() -> Self {
     Self {
-      pixels: vec![vec![false; 50]; 6],
+      pixels: Grid::new(50, 6, false),
     }
   }
 
@@ -31,56 +32,22 @@ impl LittleScreen {
       Command::Rect { x, y } => {
         for y in 0..y {
           for x in 0..x {
-            self.pixels[y as usize][x as usize] = true;
+            self.pixels[Point::new(x as i64, y as i64)] = true;
           }
         }
       }
-      Command::RotateColumn { by, x } => {
-        let len = self.pixels.len();
-        let mut new_column = vec![false; len];
-        for i in 0..len {
-          let shifted_idx = (i + by) % len;
-          new_column[shifted_idx] = self.pixels[i][x];
-        }
-        for (i, pixel) in new_column.into_iter().enumerate() {
-          self.pixels[i][x] = pixel;
-        }
-      }
-      Command::RotateRow { by, y } => {
-        let len = self.pixels[0].len();
-        let mut new_row = vec![false; len];
-        for i in 0..len {
-          let shifted_idx = (i + by) % len;
-          new_row[shifted_idx] = self.pixels[y][i];
-        }
-        for (i, pixel) in new_row.into_iter().enumerate() {
-          self.pixels[y][i] = pixel;
-        }
-      }
+      Command::RotateColumn { by, x } => self.pixels.rotate_column(x, by),
+      Command::RotateRow { by, y } => self.pixels.rotate_row(y, by),
     }
   }
 
   fn pixels_lit(&self) -> usize {
-    self
-      .pixels
-      .iter()
-      .map(|row| row.iter().filter(|p| **p).count())
-      .sum()
+    self.pixels.iter().filter(|p| **p).count()
   }
 }
 impl Display for LittleScreen {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    for row in self.pixels.iter() {
-      for pixel_on in row.iter() {
-        if *pixel_on {
-          f.write_str("#")?;
-        } else {
-          f.write_str(".")?;
-        }
-      }
-      f.write_str("\n")?;
-    }
-    Ok(())
+    self.pixels.fmt(f)
   }
 }
 
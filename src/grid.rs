@@ -0,0 +1,451 @@
+#![allow(dead_code)]
+
+use std::{
+  collections::BTreeMap,
+  error::Error,
+  fmt::Display,
+  ops::{Add, Index, IndexMut},
+  str::FromStr,
+};
+
+/// A location on a 2D grid. `y` grows downwards, as it does when reading a
+/// puzzle input line by line.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Point {
+  pub x: i64,
+  pub y: i64,
+}
+impl Point {
+  pub const ORIGIN: Point = Point { x: 0, y: 0 };
+
+  pub fn new(x: i64, y: i64) -> Self {
+    Self { x, y }
+  }
+
+  pub fn step(self, direction: Direction) -> Self {
+    self + direction.offset()
+  }
+
+  pub fn taxicab_distance(self, other: Self) -> u64 {
+    ((self.x - other.x).abs() + (self.y - other.y).abs()) as u64
+  }
+
+  /// The points sharing an edge with this one, in `Direction::ALL` order.
+  pub fn neighbors(self) -> [Point; 4] {
+    Direction::ALL.map(|d| self.step(d))
+  }
+
+  /// The points sharing an edge or a corner with this one, clockwise from the
+  /// top left.
+  pub fn neighbors_with_diagonals(self) -> [Point; 8] {
+    [
+      (-1, -1),
+      (0, -1),
+      (1, -1),
+      (1, 0),
+      (1, 1),
+      (0, 1),
+      (-1, 1),
+      (-1, 0),
+    ]
+    .map(|(x, y)| self + Point::new(x, y))
+  }
+
+  /// Whether the point is inside a `width` by `height` grid with its top left
+  /// corner at the origin.
+  pub fn within(self, width: i64, height: i64) -> bool {
+    self.x >= 0 && self.y >= 0 && self.x < width && self.y < height
+  }
+}
+impl Add for Point {
+  type Output = Point;
+
+  fn add(self, other: Point) -> Point {
+    Point::new(self.x + other.x, self.y + other.y)
+  }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Direction {
+  Up,
+  Down,
+  Left,
+  Right,
+}
+impl Direction {
+  pub const ALL: [Direction; 4] = [
+    Direction::Up,
+    Direction::Down,
+    Direction::Left,
+    Direction::Right,
+  ];
+
+  pub fn offset(self) -> Point {
+    match self {
+      Direction::Up => Point::new(0, -1),
+      Direction::Down => Point::new(0, 1),
+      Direction::Left => Point::new(-1, 0),
+      Direction::Right => Point::new(1, 0),
+    }
+  }
+
+  pub fn as_char(self) -> char {
+    match self {
+      Direction::Up => 'U',
+      Direction::Down => 'D',
+      Direction::Left => 'L',
+      Direction::Right => 'R',
+    }
+  }
+
+  pub fn from_char(c: char) -> Option<Self> {
+    match c {
+      'U' => Some(Direction::Up),
+      'D' => Some(Direction::Down),
+      'L' => Some(Direction::Left),
+      'R' => Some(Direction::Right),
+      _ => None,
+    }
+  }
+
+  pub fn reverse(self) -> Self {
+    match self {
+      Direction::Up => Direction::Down,
+      Direction::Down => Direction::Up,
+      Direction::Left => Direction::Right,
+      Direction::Right => Direction::Left,
+    }
+  }
+}
+
+/// A value that can be drawn as a single character of ASCII art.
+pub trait Cell: Sized {
+  fn from_char(c: char) -> Option<Self>;
+  fn to_char(&self) -> char;
+}
+impl Cell for bool {
+  fn from_char(c: char) -> Option<Self> {
+    match c {
+      '#' => Some(true),
+      '.' => Some(false),
+      _ => None,
+    }
+  }
+
+  fn to_char(&self) -> char {
+    if *self {
+      '#'
+    } else {
+      '.'
+    }
+  }
+}
+impl Cell for char {
+  fn from_char(c: char) -> Option<Self> {
+    Some(c)
+  }
+
+  fn to_char(&self) -> char {
+    *self
+  }
+}
+/// Spaces are empty, e.g. the gaps around a diamond shaped keypad.
+impl Cell for Option<char> {
+  fn from_char(c: char) -> Option<Self> {
+    match c {
+      ' ' => Some(None),
+      c => Some(Some(c)),
+    }
+  }
+
+  fn to_char(&self) -> char {
+    self.unwrap_or(' ')
+  }
+}
+
+/// A fixed size rectangular grid, stored row by row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+  width: usize,
+  height: usize,
+  cells: Vec<T>,
+}
+impl<T> Grid<T> {
+  pub fn new(width: usize, height: usize, fill: T) -> Self
+  where
+    T: Clone,
+  {
+    Self {
+      width,
+      height,
+      cells: vec![fill; width * height],
+    }
+  }
+
+  pub fn width(&self) -> usize {
+    self.width
+  }
+
+  pub fn height(&self) -> usize {
+    self.height
+  }
+
+  pub fn contains(&self, point: Point) -> bool {
+    point.within(self.width as i64, self.height as i64)
+  }
+
+  pub fn get(&self, point: Point) -> Option<&T> {
+    if self.contains(point) {
+      Some(&self.cells[self.offset(point)])
+    } else {
+      None
+    }
+  }
+
+  pub fn get_mut(&mut self, point: Point) -> Option<&mut T> {
+    if self.contains(point) {
+      let offset = self.offset(point);
+      Some(&mut self.cells[offset])
+    } else {
+      None
+    }
+  }
+
+  /// Every point in the grid, row by row.
+  pub fn points(&self) -> impl Iterator<Item = Point> {
+    let (width, height) = (self.width as i64, self.height as i64);
+    (0..height).flat_map(move |y| (0..width).map(move |x| Point::new(x, y)))
+  }
+
+  /// Every cell in the grid, row by row.
+  pub fn iter(&self) -> impl Iterator<Item = &T> {
+    self.cells.iter()
+  }
+
+  pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+    self.cells.chunks(self.width.max(1)).take(self.height)
+  }
+
+  /// The first point whose cell satisfies `predicate`, row by row.
+  pub fn position(&self, predicate: impl Fn(&T) -> bool) -> Option<Point> {
+    self.points().find(|p| predicate(&self[*p]))
+  }
+
+  /// The neighbors of `point` which are inside the grid.
+  pub fn neighbors(&self, point: Point) -> impl Iterator<Item = Point> + '_ {
+    IntoIterator::into_iter(point.neighbors()).filter(move |p| self.contains(*p))
+  }
+
+  /// Shifts row `y` right by `by`, wrapping around.
+  pub fn rotate_row(&mut self, y: usize, by: usize) {
+    let width = self.width;
+    self.cells[y * width..(y + 1) * width].rotate_right(by % width);
+  }
+
+  /// Shifts column `x` down by `by`, wrapping around.
+  pub fn rotate_column(&mut self, x: usize, by: usize)
+  where
+    T: Clone,
+  {
+    let column: Vec<T> = (0..self.height)
+      .map(|y| self.cells[y * self.width + x].clone())
+      .collect();
+    for (y, cell) in column.into_iter().enumerate() {
+      let y = (y + by) % self.height;
+      self.cells[y * self.width + x] = cell;
+    }
+  }
+
+  fn offset(&self, point: Point) -> usize {
+    point.y as usize * self.width + point.x as usize
+  }
+}
+impl<T> Index<Point> for Grid<T> {
+  type Output = T;
+
+  fn index(&self, point: Point) -> &T {
+    self.get(point).expect("Point outside of the grid")
+  }
+}
+impl<T> IndexMut<Point> for Grid<T> {
+  fn index_mut(&mut self, point: Point) -> &mut T {
+    self.get_mut(point).expect("Point outside of the grid")
+  }
+}
+/// Parses ASCII art, one row per line. Every line must be the same length.
+impl<T: Cell> FromStr for Grid<T> {
+  type Err = Box<dyn Error>;
+
+  fn from_str(art: &str) -> Result<Self, Self::Err> {
+    let mut width = None;
+    let mut height = 0;
+    let mut cells = Vec::new();
+    for line in art.lines() {
+      let len = line.chars().count();
+      if *width.get_or_insert(len) != len {
+        return Err(format!("Ragged grid, line {} has length {}", height + 1, len).into());
+      }
+      for c in line.chars() {
+        cells.push(T::from_char(c).ok_or_else(|| format!("Unexpected grid cell: {:?}", c))?);
+      }
+      height += 1;
+    }
+    Ok(Self {
+      width: width.unwrap_or(0),
+      height,
+      cells,
+    })
+  }
+}
+impl<T: Cell> Display for Grid<T> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    for row in self.rows() {
+      for cell in row {
+        write!(f, "{}", cell.to_char())?;
+      }
+      f.write_str("\n")?;
+    }
+    Ok(())
+  }
+}
+
+/// A grid with no fixed size, which only stores the cells that are set.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SparseGrid<T> {
+  cells: BTreeMap<Point, T>,
+}
+impl<T> SparseGrid<T> {
+  pub fn new() -> Self {
+    Self {
+      cells: BTreeMap::new(),
+    }
+  }
+
+  pub fn get(&self, point: Point) -> Option<&T> {
+    self.cells.get(&point)
+  }
+
+  pub fn insert(&mut self, point: Point, value: T) -> Option<T> {
+    self.cells.insert(point, value)
+  }
+
+  pub fn remove(&mut self, point: Point) -> Option<T> {
+    self.cells.remove(&point)
+  }
+
+  pub fn len(&self) -> usize {
+    self.cells.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.cells.is_empty()
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+    self.cells.iter().map(|(p, v)| (*p, v))
+  }
+
+  /// The top left and bottom right corners of the smallest rectangle
+  /// containing every set cell.
+  pub fn bounds(&self) -> Option<(Point, Point)> {
+    let mut points = self.cells.keys();
+    let first = *points.next()?;
+    Some(points.fold((first, first), |(min, max), p| {
+      (
+        Point::new(min.x.min(p.x), min.y.min(p.y)),
+        Point::new(max.x.max(p.x), max.y.max(p.y)),
+      )
+    }))
+  }
+}
+/// Draws the cells within `bounds`, with unset cells drawn as the default.
+impl<T: Cell + Default> Display for SparseGrid<T> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let (min, max) = match self.bounds() {
+      Some(bounds) => bounds,
+      None => return Ok(()),
+    };
+    let blank = T::default().to_char();
+    for y in min.y..=max.y {
+      for x in min.x..=max.x {
+        let c = match self.get(Point::new(x, y)) {
+          Some(cell) => cell.to_char(),
+          None => blank,
+        };
+        write!(f, "{}", c)?;
+      }
+      f.write_str("\n")?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn points_and_directions() {
+    let p = Point::new(2, 3);
+    assert_eq!(Point::new(2, 2), p.step(Direction::Up));
+    assert_eq!(5, p.taxicab_distance(Point::ORIGIN));
+    assert_eq!(
+      [
+        Point::new(2, 2),
+        Point::new(2, 4),
+        Point::new(1, 3),
+        Point::new(3, 3)
+      ],
+      p.neighbors()
+    );
+    assert_eq!(8, p.neighbors_with_diagonals().len());
+    assert!(!p.neighbors_with_diagonals().contains(&p));
+    assert!(p.within(3, 4));
+    assert!(!p.within(2, 4));
+    for direction in Direction::ALL.iter() {
+      assert_eq!(Some(*direction), Direction::from_char(direction.as_char()));
+      assert_eq!(p, p.step(*direction).step(direction.reverse()));
+    }
+  }
+
+  #[test]
+  fn parse_and_display() {
+    let art = "#..\n.#.\n";
+    let mut grid: Grid<bool> = art.parse().unwrap();
+    assert_eq!((3, 2), (grid.width(), grid.height()));
+    assert!(grid[Point::new(1, 1)]);
+    assert_eq!(None, grid.get(Point::new(3, 0)));
+    assert_eq!(art, grid.to_string());
+    grid[Point::new(2, 0)] = true;
+    assert_eq!("#.#\n.#.\n", grid.to_string());
+    assert_eq!(
+      vec![Point::new(0, 1), Point::new(1, 0)],
+      grid.neighbors(Point::ORIGIN).collect::<Vec<_>>()
+    );
+    assert!("#.\n#\n".parse::<Grid<bool>>().is_err());
+    assert!("#x\n".parse::<Grid<bool>>().is_err());
+
+    let keypad: Grid<Option<char>> = " 1 \n234\n".parse().unwrap();
+    assert_eq!(None, keypad[Point::ORIGIN]);
+    assert_eq!(Some(Point::new(1, 1)), keypad.position(|c| *c == Some('3')));
+  }
+
+  #[test]
+  fn rotation() {
+    let mut grid: Grid<char> = "abc\ndef\n".parse().unwrap();
+    grid.rotate_row(0, 4);
+    assert_eq!("cab\ndef\n", grid.to_string());
+    grid.rotate_column(2, 1);
+    assert_eq!("caf\ndeb\n", grid.to_string());
+  }
+
+  #[test]
+  fn sparse() {
+    let mut grid = SparseGrid::new();
+    assert_eq!("", grid.to_string());
+    grid.insert(Point::new(-1, 5), true);
+    grid.insert(Point::new(1, 6), true);
+    assert_eq!(Some((Point::new(-1, 5), Point::new(1, 6))), grid.bounds());
+    assert_eq!("#..\n..#\n", grid.to_string());
+  }
+}
@@ -14,6 +14,10 @@ mod day_9;
 mod day_10;
 mod day_11;
 mod day_12;
+mod day_12_cfg;
+mod day_12_debugger;
+mod day_12_decompile;
+mod day_12_trace;
 mod day_13;
 mod day_14;
 mod day_15;
@@ -21,8 +25,44 @@ mod day_16;
 mod day_17;
 
 mod astar;
+mod grid;
 mod md5;
+mod number_theory;
+mod ppm;
+mod sha;
+#[cfg(test)]
+mod test_util;
+mod tsp;
 
 fn main() {
-  println!("{}", "See the individual files and their tests!");
+  let args: Vec<String> = std::env::args().collect();
+  let args: Vec<&str> = args.iter().map(|a| a.as_str()).collect();
+  let result = match args[1..] {
+    ["debug", path] => day_12_debugger::debug_file(path),
+    ["trace", path] => day_12_trace::profile_file(path, None),
+    ["trace", path, trace_path] => day_12_trace::profile_file(path, Some(trace_path)),
+    ["fmt", path] => day_12::print_formatted_file(path),
+    ["cfg", path] => day_12_cfg::print_dot_file(path),
+    ["decompile", path] => day_12_decompile::print_decompiled_file(path),
+    ["crack", door_id] => day_5::crack_cinematically(door_id),
+    ["maze", favorite_number, dir] => day_13::export_search_animations(favorite_number, dir),
+    ["pad", salt] => day_14::print_part_2(salt, None),
+    ["pad", salt, cache_path] => day_14::print_part_2(salt, Some(cache_path)),
+    _ => {
+      println!("See the individual files and their tests!");
+      println!("Usage: advent-2016 debug <assembunny program>");
+      println!("       advent-2016 trace <assembunny program> [trace output]");
+      println!("       advent-2016 fmt <assembunny program>");
+      println!("       advent-2016 cfg <assembunny program>");
+      println!("       advent-2016 decompile <assembunny program>");
+      println!("       advent-2016 crack <door id>");
+      println!("       advent-2016 maze <favorite number> <output directory>");
+      println!("       advent-2016 pad <salt> [stretched hash cache]");
+      Ok(())
+    }
+  };
+  if let Err(e) = result {
+    eprintln!("{}", e);
+    std::process::exit(1);
+  }
 }
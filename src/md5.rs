@@ -1,63 +1,917 @@
 #![allow(dead_code)]
 
-use std::fmt::Write;
-
 use md5::Digest;
-pub struct Md5Iterator {
-  len: usize,
-  i: u64,
-  scratch_str: String,
+use rayon::{prelude::*, ThreadPool, ThreadPoolBuildError, ThreadPoolBuilder};
+use smallvec::SmallVec;
+use std::{fmt, marker::PhantomData, ops::Range};
+
+// Per-round shift amounts and constants, from RFC 1321.
+const S: [u32; 64] = [
+  7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 5, 9, 14, 20, 5, 9, 14, 20, 5, 9, 14,
+  20, 5, 9, 14, 20, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 6, 10, 15, 21, 6,
+  10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21,
+];
+const K: [u32; 64] = [
+  0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee, 0xf57c0faf, 0x4787c62a, 0xa8304613, 0xfd469501,
+  0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be, 0x6b901122, 0xfd987193, 0xa679438e, 0x49b40821,
+  0xf61e2562, 0xc040b340, 0x265e5a51, 0xe9b6c7aa, 0xd62f105d, 0x02441453, 0xd8a1e681, 0xe7d3fbc8,
+  0x21e1cde6, 0xc33707d6, 0xf4d50d87, 0x455a14ed, 0xa9e3e905, 0xfcefa3f8, 0x676f02d9, 0x8d2a4c8a,
+  0xfffa3942, 0x8771f681, 0x6d9d6122, 0xfde5380c, 0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70,
+  0x289b7ec6, 0xeaa127fa, 0xd4ef3085, 0x04881d05, 0xd9d4d039, 0xe6db99e5, 0x1fa27cf8, 0xc4ac5665,
+  0xf4292244, 0x432aff97, 0xab9423a7, 0xfc93a039, 0x655b59c3, 0x8f0ccc92, 0xffeff47d, 0x85845dd1,
+  0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1, 0xf7537e82, 0xbd3af235, 0x2ad7d2bb, 0xeb86d391,
+];
+const INITIAL_STATE: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+
+/// How many messages `compress` hashes side by side when brute forcing. Each
+/// step of the rounds is a loop over the lanes, which the compiler can turn
+/// into SIMD instructions.
+pub const LANES: usize = 8;
+
+// The longest message which fits, with its padding, in a single block.
+const MAX_SINGLE_BLOCK: usize = 55;
+
+/// Runs the MD5 compression function on `N` independent states at once.
+fn compress<const N: usize>(states: &mut [[u32; 4]; N], blocks: &[[u32; 16]; N]) {
+  // Word-major, so each step reads the same word of every lane from
+  // consecutive memory.
+  let words: [[u32; N]; 16] = std::array::from_fn(|w| std::array::from_fn(|l| blocks[l][w]));
+  compress_words(states, &words);
+}
+
+/// `compress`, with the blocks already transposed so `words[w][l]` is word
+/// `w` of lane `l`'s block.
+fn compress_words<const N: usize>(states: &mut [[u32; 4]; N], words: &[[u32; N]; 16]) {
+  let mut a: [u32; N] = std::array::from_fn(|l| states[l][0]);
+  let mut b: [u32; N] = std::array::from_fn(|l| states[l][1]);
+  let mut c: [u32; N] = std::array::from_fn(|l| states[l][2]);
+  let mut d: [u32; N] = std::array::from_fn(|l| states[l][3]);
+  for i in 0..64 {
+    let (round, g) = match i / 16 {
+      0 => (0, i),
+      1 => (1, (5 * i + 1) % 16),
+      2 => (2, (3 * i + 5) % 16),
+      _ => (3, (7 * i) % 16),
+    };
+    for ((((a, b), c), d), word) in a
+      .iter_mut()
+      .zip(b.iter_mut())
+      .zip(c.iter_mut())
+      .zip(d.iter_mut())
+      .zip(words[g].iter())
+    {
+      let f = match round {
+        0 => (*b & *c) | (!*b & *d),
+        1 => (*d & *b) | (!*d & *c),
+        2 => *b ^ *c ^ *d,
+        _ => *c ^ (*b | !*d),
+      };
+      let sum = a.wrapping_add(f).wrapping_add(K[i]).wrapping_add(*word);
+      let rotated = b.wrapping_add(sum.rotate_left(S[i]));
+      *a = *d;
+      *d = *c;
+      *c = *b;
+      *b = rotated;
+    }
+  }
+  for (l, state) in states.iter_mut().enumerate() {
+    state[0] = state[0].wrapping_add(a[l]);
+    state[1] = state[1].wrapping_add(b[l]);
+    state[2] = state[2].wrapping_add(c[l]);
+    state[3] = state[3].wrapping_add(d[l]);
+  }
+}
+
+fn block_words(block: &[u8; 64]) -> [u32; 16] {
+  std::array::from_fn(|i| {
+    u32::from_le_bytes([
+      block[4 * i],
+      block[4 * i + 1],
+      block[4 * i + 2],
+      block[4 * i + 3],
+    ])
+  })
+}
+
+fn state_digest(state: &[u32; 4]) -> Digest {
+  let mut digest = [0; 16];
+  for (bytes, word) in digest.chunks_mut(4).zip(state.iter()) {
+    bytes.copy_from_slice(&word.to_le_bytes());
+  }
+  Digest(digest)
+}
+
+/// The padded block for a message of at most `MAX_SINGLE_BLOCK` bytes.
+fn single_block(message: &[u8]) -> [u32; 16] {
+  let mut block = [0; 64];
+  block[..message.len()].copy_from_slice(message);
+  block[message.len()] = 0x80;
+  block[56..].copy_from_slice(&(message.len() as u64 * 8).to_le_bytes());
+  block_words(&block)
+}
+
+/// An MD5 hasher which can be fed its input a piece at a time.
+#[derive(Clone)]
+pub struct Md5 {
+  state: [u32; 4],
+  buffer: [u8; 64],
+  buffered: usize,
+  length: u64,
+}
+impl Md5 {
+  pub fn new() -> Self {
+    Self {
+      state: INITIAL_STATE,
+      buffer: [0; 64],
+      buffered: 0,
+      length: 0,
+    }
+  }
+
+  pub fn update(&mut self, mut data: &[u8]) {
+    self.length += data.len() as u64;
+    while !data.is_empty() {
+      let take = (64 - self.buffered).min(data.len());
+      self.buffer[self.buffered..self.buffered + take].copy_from_slice(&data[..take]);
+      self.buffered += take;
+      data = &data[take..];
+      if self.buffered == 64 {
+        let mut states = [self.state];
+        compress(&mut states, &[block_words(&self.buffer)]);
+        self.state = states[0];
+        self.buffered = 0;
+      }
+    }
+  }
+
+  /// The padded last block of the message, if `suffix` fits in it along with
+  /// what's buffered. This lets many messages sharing a prefix each be
+  /// finished with a single compression from this state.
+  fn last_block(&self, suffix: &[u8]) -> Option<[u32; 16]> {
+    let len = self.buffered + suffix.len();
+    if len > MAX_SINGLE_BLOCK {
+      return None;
+    }
+    let mut block = [0; 64];
+    block[..self.buffered].copy_from_slice(&self.buffer[..self.buffered]);
+    block[self.buffered..len].copy_from_slice(suffix);
+    block[len] = 0x80;
+    let bit_length = (self.length + suffix.len() as u64).wrapping_mul(8);
+    block[56..].copy_from_slice(&bit_length.to_le_bytes());
+    Some(block_words(&block))
+  }
+
+  pub fn finalize(mut self) -> Digest {
+    let bit_length = self.length.wrapping_mul(8);
+    self.update(&[0x80]);
+    while self.buffered != 56 {
+      self.update(&[0]);
+    }
+    self.update(&bit_length.to_le_bytes());
+    state_digest(&self.state)
+  }
+}
+impl Default for Md5 {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+/// A hash function the brute force helpers can run with, so the puzzles
+/// aren't tied to MD5.
+pub trait HashFunction: Clone + Default {
+  type Digest: HashBytes + Copy + PartialEq + fmt::Debug + Send + Sync;
+
+  fn update(&mut self, data: &[u8]);
+
+  fn finalize(self) -> Self::Digest;
+
+  fn compute(data: &[u8]) -> Self::Digest {
+    let mut hasher = Self::default();
+    hasher.update(data);
+    hasher.finalize()
+  }
+
+  /// Hashes the counter's next numbers, each following whatever this hasher
+  /// has already seen, pushing up to `LANES` digests and leaving the counter
+  /// at the first number not hashed.
+  fn hash_sequence(
+    &self,
+    counter: &mut DecimalCounter,
+    digests: &mut SmallVec<[Self::Digest; LANES]>,
+  ) {
+    for _ in 0..LANES {
+      let mut hasher = self.clone();
+      hasher.update(counter.digits());
+      digests.push(hasher.finalize());
+      counter.increment();
+    }
+  }
 }
-impl Md5Iterator {
+
+impl HashFunction for Md5 {
+  type Digest = Digest;
+
+  fn update(&mut self, data: &[u8]) {
+    Md5::update(self, data)
+  }
+
+  fn finalize(self) -> Digest {
+    Md5::finalize(self)
+  }
+
+  fn compute(data: &[u8]) -> Digest {
+    compute(data)
+  }
+
+  fn hash_sequence(&self, counter: &mut DecimalCounter, digests: &mut SmallVec<[Digest; LANES]>) {
+    // Appending digits makes the message longer, so once one doesn't fit in
+    // the last block none of the rest will.
+    let mut blocks = [[0; 16]; LANES];
+    let mut len = 0;
+    for block in blocks.iter_mut() {
+      match self.last_block(counter.digits()) {
+        Some(last) => *block = last,
+        None => break,
+      }
+      counter.increment();
+      len += 1;
+    }
+    if len == LANES {
+      let mut states = [self.state; LANES];
+      compress(&mut states, &blocks);
+      digests.extend(states.iter().map(state_digest));
+      return;
+    }
+    // Too long for a full batch of single blocks, so hash the stragglers
+    // one at a time.
+    for block in &blocks[..len] {
+      let mut state = [self.state];
+      compress(&mut state, &[*block]);
+      digests.push(state_digest(&state[0]));
+    }
+    if len == 0 {
+      let mut hasher = self.clone();
+      hasher.update(counter.digits());
+      digests.push(hasher.finalize());
+      counter.increment();
+    }
+  }
+}
+
+pub fn compute(data: impl AsRef<[u8]>) -> Digest {
+  let data = data.as_ref();
+  if data.len() <= MAX_SINGLE_BLOCK {
+    let mut state = [INITIAL_STATE];
+    compress(&mut state, &[single_block(data)]);
+    return state_digest(&state[0]);
+  }
+  let mut hasher = Md5::new();
+  hasher.update(data);
+  hasher.finalize()
+}
+
+/// Hashes `N` messages at once, side by side when they're short enough to
+/// fit in a single block.
+pub fn compute_batch<const N: usize>(messages: [&[u8]; N]) -> [Digest; N] {
+  if messages.iter().any(|m| m.len() > MAX_SINGLE_BLOCK) {
+    return messages.map(compute);
+  }
+  let mut states = [INITIAL_STATE; N];
+  compress(&mut states, &messages.map(single_block));
+  std::array::from_fn(|l| state_digest(&states[l]))
+}
+
+/// Replaces each digest with the digest of its lowercase hex, `rounds` times
+/// over, running the `N` chains side by side. This is the key stretching
+/// from day 14, without any encoding or padding work between rounds.
+pub fn stretch_batch<const N: usize>(digests: &mut [Digest; N], rounds: usize) {
+  let mut states: [[u32; 4]; N] = std::array::from_fn(|l| {
+    std::array::from_fn(|w| {
+      let bytes = &digests[l].0[4 * w..4 * w + 4];
+      u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+    })
+  });
+  // 32 hex characters, then the padding and the length in bits.
+  let mut words = [[0; N]; 16];
+  words[8] = [0x80; N];
+  words[14] = [32 * 8; N];
+  for _ in 0..rounds {
+    for (w, word) in words[..8].iter_mut().enumerate() {
+      for (hex, state) in word.iter_mut().zip(states.iter()) {
+        // Two bytes of the digest make four hex characters.
+        let bytes = state[w / 2] >> (16 * (w % 2));
+        *hex = hex_pair(bytes & 0xff) | (hex_pair((bytes >> 8) & 0xff) << 16);
+      }
+    }
+    states = [INITIAL_STATE; N];
+    compress_words(&mut states, &words);
+  }
+  for (digest, state) in digests.iter_mut().zip(states.iter()) {
+    *digest = state_digest(state);
+  }
+}
+
+/// The two lowercase hex characters of `byte`, first character lowest.
+fn hex_pair(byte: u32) -> u32 {
+  hex_char(byte >> 4) | (hex_char(byte & 0x0f) << 8)
+}
+
+fn hex_char(nibble: u32) -> u32 {
+  // (nibble + 6) >> 4 is 1 for a to f, skipping from '9' to 'a'.
+  nibble + b'0' as u32 + ((nibble + 6) >> 4) * (b'a' - b'9' - 1) as u32
+}
+
+/// The lowercase hex encoding of a digest, without allocating.
+pub fn to_hex(digest: &Digest) -> [u8; 32] {
+  let mut result = [0; 32];
+  result.copy_from_slice(Nibbles::new(digest).encode_hex().as_bytes());
+  result
+}
+
+/// A prefix followed by a decimal number, which is incremented in place
+/// rather than formatted from scratch each time.
+pub struct DecimalCounter {
+  bytes: Vec<u8>,
+  prefix_len: usize,
+}
+impl DecimalCounter {
+  pub fn new(prefix: &str, start: u64) -> Self {
+    let mut bytes = prefix.as_bytes().to_vec();
+    bytes.extend_from_slice(start.to_string().as_bytes());
+    Self {
+      bytes,
+      prefix_len: prefix.len(),
+    }
+  }
+
+  /// The prefix and the current number.
+  pub fn as_bytes(&self) -> &[u8] {
+    &self.bytes
+  }
+
+  /// Just the current number.
+  pub fn digits(&self) -> &[u8] {
+    &self.bytes[self.prefix_len..]
+  }
+
+  pub fn increment(&mut self) {
+    for digit in self.bytes[self.prefix_len..].iter_mut().rev() {
+      if *digit == b'9' {
+        *digit = b'0';
+      } else {
+        *digit += 1;
+        return;
+      }
+    }
+    // Every digit was a 9, e.g. 999 became 000, so it needs to be 1000.
+    self.bytes.insert(self.prefix_len, b'1');
+  }
+}
+
+/// The digests of a prefix followed by 0, 1, 2, etc, computed up to `LANES`
+/// at a time. The prefix is only hashed once, and each digest carries on from
+/// a copy of that state.
+pub struct HashIterator<H: HashFunction> {
+  prefix: H,
+  counter: DecimalCounter,
+  batch: SmallVec<[H::Digest; LANES]>,
+  next_in_batch: usize,
+}
+pub type Md5Iterator = HashIterator<Md5>;
+impl<H: HashFunction> HashIterator<H> {
   pub fn new(prefix: &str) -> Self {
+    Self::starting_at(prefix, 0)
+  }
+
+  pub fn starting_at(prefix: &str, start: u64) -> Self {
+    let mut hasher = H::default();
+    hasher.update(prefix.as_bytes());
     Self {
-      len: prefix.len(),
-      i: 0,
-      scratch_str: prefix.to_string(),
+      prefix: hasher,
+      counter: DecimalCounter::new(prefix, start),
+      batch: SmallVec::new(),
+      next_in_batch: 0,
     }
   }
 }
-impl Iterator for Md5Iterator {
-  type Item = Digest;
+impl<H: HashFunction> Iterator for HashIterator<H> {
+  type Item = H::Digest;
 
   fn next(&mut self) -> Option<Self::Item> {
-    loop {
-      self.scratch_str.truncate(self.len);
-      write!(self.scratch_str, "{}", self.i).unwrap();
-      let digest = md5::compute(&self.scratch_str);
-      self.i += 1;
-      return Some(digest);
+    if self.next_in_batch == self.batch.len() {
+      self.batch.clear();
+      self.next_in_batch = 0;
+      self
+        .prefix
+        .hash_sequence(&mut self.counter, &mut self.batch);
+    }
+    self.next_in_batch += 1;
+    Some(self.batch[self.next_in_batch - 1])
+  }
+}
+
+/// The raw bytes of a hash, so code looking for patterns in hashes isn't tied
+/// to MD5 digests.
+pub trait HashBytes {
+  fn hash_bytes(&self) -> &[u8];
+}
+impl HashBytes for Digest {
+  fn hash_bytes(&self) -> &[u8] {
+    &self.0
+  }
+}
+impl HashBytes for Vec<u8> {
+  fn hash_bytes(&self) -> &[u8] {
+    self
+  }
+}
+impl<const N: usize> HashBytes for [u8; N] {
+  fn hash_bytes(&self) -> &[u8] {
+    self
+  }
+}
+
+/// The lowercase hex digit for a nibble.
+pub fn hex_digit(nibble: u8) -> char {
+  hex_char(nibble as u32) as u8 as char
+}
+
+/// A digest, or any other hash, as a sequence of hex digits.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Nibbles<'a> {
+  bytes: &'a [u8],
+}
+impl<'a> Nibbles<'a> {
+  pub fn new<H: HashBytes + ?Sized>(hash: &'a H) -> Self {
+    Self {
+      bytes: hash.hash_bytes(),
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    self.bytes.len() * 2
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.bytes.is_empty()
+  }
+
+  /// The hex digit at `n`, counting from the first digit. Panics if `n` is
+  /// past the end.
+  pub fn nth_nibble(&self, n: usize) -> u8 {
+    let byte = self.bytes[n / 2];
+    if n & 1 == 0 {
+      byte >> 4
+    } else {
+      byte & 0x0f
+    }
+  }
+
+  pub fn leading_zero_nibbles(&self) -> usize {
+    match self.bytes.iter().position(|b| *b != 0) {
+      Some(i) if self.bytes[i] >> 4 == 0 => 2 * i + 1,
+      Some(i) => 2 * i,
+      None => self.len(),
+    }
+  }
+
+  /// The runs of the same digit, in order, as (digit, run length).
+  pub fn runs(&self) -> Runs<'a> {
+    Runs {
+      nibbles: *self,
+      idx: 0,
+    }
+  }
+
+  /// Whether the same digit appears at least `len` times in a row.
+  pub fn has_run(&self, len: usize) -> bool {
+    self.runs().any(|(_, run)| run >= len)
+  }
+
+  pub fn iter(&self) -> impl DoubleEndedIterator<Item = u8> + ExactSizeIterator + 'a {
+    let nibbles = *self;
+    (0..self.len()).map(move |n| nibbles.nth_nibble(n))
+  }
+
+  /// The lowercase hex encoding, without allocating. Panics for hashes
+  /// longer than 32 bytes.
+  pub fn encode_hex(&self) -> HexString {
+    let mut hex = HexString {
+      buffer: [0; HEX_STRING_CAPACITY],
+      len: self.len(),
+    };
+    for (i, byte) in self.bytes.iter().enumerate() {
+      let pair = hex_pair(*byte as u32);
+      hex.buffer[2 * i] = pair as u8;
+      hex.buffer[2 * i + 1] = (pair >> 8) as u8;
     }
+    hex
+  }
+}
+impl fmt::Display for Nibbles<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(self.encode_hex().as_str())
   }
 }
 
-pub struct HexIterator {
-  digest: Digest,
+pub struct Runs<'a> {
+  nibbles: Nibbles<'a>,
   idx: usize,
-  lower: bool,
 }
-impl HexIterator {
-  pub fn new(digest: Digest) -> Self {
+impl Iterator for Runs<'_> {
+  type Item = (u8, usize);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.idx == self.nibbles.len() {
+      return None;
+    }
+    let nibble = self.nibbles.nth_nibble(self.idx);
+    let start = self.idx;
+    while self.idx < self.nibbles.len() && self.nibbles.nth_nibble(self.idx) == nibble {
+      self.idx += 1;
+    }
+    Some((nibble, self.idx - start))
+  }
+}
+
+// Enough for the hex of a 256 bit hash.
+const HEX_STRING_CAPACITY: usize = 64;
+
+/// Hex digits held on the stack.
+#[derive(Copy, Clone)]
+pub struct HexString {
+  buffer: [u8; HEX_STRING_CAPACITY],
+  len: usize,
+}
+impl HexString {
+  pub fn as_bytes(&self) -> &[u8] {
+    &self.buffer[..self.len]
+  }
+
+  pub fn as_str(&self) -> &str {
+    // Only ever holds ASCII hex digits.
+    std::str::from_utf8(self.as_bytes()).unwrap()
+  }
+}
+impl fmt::Debug for HexString {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    fmt::Debug::fmt(self.as_str(), f)
+  }
+}
+
+/// The hex digits of a digest, as numbers from 0 to 15.
+pub struct HexIterator<D: HashBytes = Digest> {
+  digest: D,
+  front: usize,
+  back: usize,
+}
+impl<D: HashBytes> HexIterator<D> {
+  pub fn new(digest: D) -> Self {
+    let back = digest.hash_bytes().len() * 2;
     Self {
       digest,
-      idx: 0,
-      lower: false,
+      front: 0,
+      back,
     }
   }
 }
-impl Iterator for HexIterator {
+impl<D: HashBytes> Iterator for HexIterator<D> {
   type Item = u8;
 
   fn next(&mut self) -> Option<Self::Item> {
-    let initial = *self.digest.0.get(self.idx)?;
-    let c = if self.lower {
-      self.lower = false;
-      self.idx += 1;
-      initial & 0x0f
-    } else {
-      self.lower = true;
-      (initial & 0xf0) >> 4
-    };
-    Some(c)
+    if self.front == self.back {
+      return None;
+    }
+    self.front += 1;
+    Some(Nibbles::new(&self.digest).nth_nibble(self.front - 1))
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    let len = self.back - self.front;
+    (len, Some(len))
+  }
+}
+impl<D: HashBytes> DoubleEndedIterator for HexIterator<D> {
+  fn next_back(&mut self) -> Option<Self::Item> {
+    if self.front == self.back {
+      return None;
+    }
+    self.back -= 1;
+    Some(Nibbles::new(&self.digest).nth_nibble(self.back))
+  }
+}
+impl<D: HashBytes> ExactSizeIterator for HexIterator<D> {}
+
+/// Checks the raw bytes of a digest.
+pub type DigestFilter = dyn Fn(&[u8]) -> bool + Send + Sync;
+
+/// What a miner is looking for in a digest.
+pub enum Predicate {
+  // The hex digest starts with this many zeros.
+  LeadingZeroNibbles(usize),
+  // The hex digest has the same digit at least this many times in a row.
+  ContainsRun(usize),
+  Custom(Box<DigestFilter>),
+}
+impl Predicate {
+  pub fn matches<D: HashBytes>(&self, digest: &D) -> bool {
+    match self {
+      Predicate::LeadingZeroNibbles(n) => Nibbles::new(digest).leading_zero_nibbles() >= *n,
+      Predicate::ContainsRun(n) => Nibbles::new(digest).has_run(*n),
+      Predicate::Custom(matches) => matches(digest.hash_bytes()),
+    }
+  }
+}
+
+/// Searches the digests of a prefix followed by each index in a range for
+/// ones matching a predicate, on a pool of threads.
+pub struct Miner<H: HashFunction = Md5> {
+  // Only names the hash function, so the miner is Sync whatever it is.
+  hash: PhantomData<fn() -> H>,
+  prefix: String,
+  predicate: Predicate,
+  range: Range<u64>,
+  chunk_size: u64,
+  threads: Option<usize>,
+}
+impl<H: HashFunction> Miner<H> {
+  pub fn new(prefix: &str, predicate: Predicate) -> Self {
+    Self {
+      hash: PhantomData,
+      prefix: prefix.to_string(),
+      predicate,
+      range: 0..u64::MAX,
+      chunk_size: 1024 * 1024,
+      threads: None,
+    }
+  }
+
+  pub fn range(mut self, range: Range<u64>) -> Self {
+    self.range = range;
+    self
+  }
+
+  /// How many threads to hash on. Defaults to one per CPU.
+  pub fn threads(mut self, threads: usize) -> Self {
+    self.threads = Some(threads);
+    self
+  }
+
+  /// How many indexes to search between checking whether we've found enough.
+  /// A size of 0 is treated as 1, so that the search still makes progress.
+  pub fn chunk_size(mut self, chunk_size: u64) -> Self {
+    self.chunk_size = chunk_size.max(1);
+    self
+  }
+
+  /// The first `k` matches, as (index, digest), in index order.
+  pub fn first(&self, k: usize) -> Result<Vec<(u64, H::Digest)>, ThreadPoolBuildError> {
+    let mut result = Vec::with_capacity(k);
+    for chunk in self.chunks()? {
+      result.extend(chunk);
+      if result.len() >= k {
+        break;
+      }
+    }
+    result.truncate(k);
+    Ok(result)
+  }
+
+  /// The matches in each successive chunk of the range, in index order.
+  /// Searching stops when the caller stops asking for chunks. The threads
+  /// are started here, and live as long as the iterator.
+  pub fn chunks(
+    &self,
+  ) -> Result<impl Iterator<Item = Vec<(u64, H::Digest)>> + '_, ThreadPoolBuildError> {
+    let mut builder = ThreadPoolBuilder::new();
+    if let Some(threads) = self.threads {
+      builder = builder.num_threads(threads);
+    }
+    let pool = builder.build()?;
+    let mut start = self.range.start;
+    Ok(std::iter::from_fn(move || {
+      if start >= self.range.end {
+        return None;
+      }
+      let end = start.saturating_add(self.chunk_size).min(self.range.end);
+      let chunk = self.search(&pool, start..end);
+      start = end;
+      Some(chunk)
+    }))
+  }
+
+  /// The matches in `range`, in index order. Collecting keeps each task's
+  /// matches in task order whichever thread finished first, and however many
+  /// matches there are.
+  fn search(&self, pool: &ThreadPool, range: Range<u64>) -> Vec<(u64, H::Digest)> {
+    let task_size = MINER_TASK_SIZE;
+    let tasks = (range.end - range.start).div_ceil(task_size);
+    pool.install(|| {
+      (0..tasks)
+        .into_par_iter()
+        .flat_map_iter(|task| {
+          let start = range.start + task * task_size;
+          let end = (start + task_size).min(range.end);
+          (start..end)
+            .zip(HashIterator::<H>::starting_at(&self.prefix, start))
+            .filter(|(_, digest)| self.predicate.matches(digest))
+        })
+        .collect()
+    })
+  }
+}
+
+// How many consecutive indexes each thread hashes at a time, so it can reuse
+// its counter and batch them.
+const MINER_TASK_SIZE: u64 = 4096;
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn hex(digest: Digest) -> String {
+    format!("{:x}", digest)
+  }
+
+  #[test]
+  fn known_digests() {
+    assert_eq!("d41d8cd98f00b204e9800998ecf8427e", hex(compute("")));
+    assert_eq!("900150983cd24fb0d6963f7d28e17f72", hex(compute("abc")));
+    assert_eq!(
+      b"900150983cd24fb0d6963f7d28e17f72",
+      &to_hex(&compute("abc"))
+    );
+    assert_eq!(
+      "57edf4a22be3c955ac49da2e2107b67a",
+      hex(compute(
+        "12345678901234567890123456789012345678901234567890123456789012345678901234567890"
+      ))
+    );
+    for len in 50..140 {
+      let message = "x".repeat(len);
+      assert_eq!(md5::compute(&message), compute(&message), "{}", len);
+    }
+  }
+
+  #[test]
+  fn batches() {
+    let long = "y".repeat(70);
+    assert_eq!(
+      [compute("abc"), compute(""), compute("abc18")],
+      compute_batch([b"abc", b"", b"abc18"])
+    );
+    assert_eq!(
+      [compute(&long), compute("abc")],
+      compute_batch([long.as_bytes(), b"abc"])
+    );
+  }
+
+  #[test]
+  fn stretching() {
+    let mut digests = [compute("abc0"), compute("abc1"), compute("xyz")];
+    let mut expected = digests;
+    for digest in expected.iter_mut() {
+      for _ in 0..5 {
+        *digest = compute(to_hex(digest));
+      }
+    }
+    stretch_batch(&mut digests, 5);
+    assert_eq!(expected, digests);
+  }
+
+  #[test]
+  fn streaming() {
+    let mut hasher = Md5::new();
+    for piece in ["The quick brown fox ", "jumps over ", "the lazy dog"].iter() {
+      hasher.update(piece.as_bytes());
+    }
+    assert_eq!("9e107d9d372bb6826bd81d3542a419d6", hex(hasher.finalize()));
+  }
+
+  #[test]
+  fn decimal_counter() {
+    let mut counter = DecimalCounter::new("abc", 98);
+    let mut seen = Vec::new();
+    for _ in 0..4 {
+      seen.push(String::from_utf8(counter.as_bytes().to_vec()).unwrap());
+      counter.increment();
+    }
+    assert_eq!(vec!["abc98", "abc99", "abc100", "abc101"], seen);
+  }
+
+  #[test]
+  fn iterator_matches_reference() {
+    // Long prefixes are past the first block, so the lanes carry on from
+    // the prefix's midstate.
+    let prefixes = [
+      "abc".to_string(),
+      "p".repeat(50),
+      "q".repeat(60),
+      "r".repeat(64),
+      "s".repeat(100),
+      "t".repeat(118),
+    ];
+    for prefix in prefixes.iter() {
+      let start = 995;
+      let expected: Vec<Digest> = (start..start + 30)
+        .map(|i| md5::compute(format!("{}{}", prefix, i)))
+        .collect();
+      let actual: Vec<Digest> = Md5Iterator::starting_at(prefix, start).take(30).collect();
+      assert_eq!(expected, actual, "{}", prefix);
+    }
+  }
+
+  #[test]
+  fn nibbles() {
+    let digest = compute("abc");
+    let nibbles = Nibbles::new(&digest);
+    assert_eq!(32, nibbles.len());
+    assert_eq!(
+      (9, 0, 0xf),
+      (
+        nibbles.nth_nibble(0),
+        nibbles.nth_nibble(1),
+        nibbles.nth_nibble(13)
+      )
+    );
+    assert_eq!(hex(digest), nibbles.to_string());
+    assert_eq!(hex(digest).as_bytes(), nibbles.encode_hex().as_bytes());
+    assert_eq!(
+      hex(digest),
+      nibbles.iter().map(hex_digit).collect::<String>()
+    );
+    assert_eq!(
+      hex(digest).chars().rev().collect::<String>(),
+      nibbles.iter().rev().map(hex_digit).collect::<String>()
+    );
+    assert_eq!(
+      vec![(9, 1), (0, 2), (1, 1), (5, 1), (0, 1), (9, 1)],
+      nibbles.runs().take(6).collect::<Vec<_>>()
+    );
+    assert_eq!(32, nibbles.runs().map(|(_, len)| len).sum::<usize>());
+    assert!(nibbles.has_run(2));
+    assert!(!nibbles.has_run(3));
+
+    assert_eq!(0, nibbles.leading_zero_nibbles());
+    assert_eq!(3, Nibbles::new(&[0u8, 0x0f, 0]).leading_zero_nibbles());
+    assert_eq!(4, Nibbles::new(&[0u8, 0, 0x10]).leading_zero_nibbles());
+    assert_eq!(6, Nibbles::new(&[0u8; 3]).leading_zero_nibbles());
+    assert_eq!("00ff", Nibbles::new(&vec![0, 255]).encode_hex().as_str());
+  }
+
+  #[test]
+  fn hex_iterator() {
+    let digest = compute("abc");
+    let mut hexes = HexIterator::new(digest);
+    assert_eq!(32, hexes.len());
+    assert_eq!((Some(9), Some(2)), (hexes.next(), hexes.next_back()));
+    assert_eq!(30, hexes.len());
+    assert_eq!(
+      "00150983cd24fb0d6963f7d28e17f7",
+      hexes.map(hex_digit).collect::<String>()
+    );
+  }
+
+  #[test]
+  fn predicates() {
+    let digest = compute("abc18");
+    // 0034e0923cc38887a57bd7b1d4f953df
+    assert!(Predicate::LeadingZeroNibbles(2).matches(&digest));
+    assert!(!Predicate::LeadingZeroNibbles(3).matches(&digest));
+    assert!(Predicate::ContainsRun(3).matches(&digest));
+    assert!(!Predicate::ContainsRun(4).matches(&digest));
+    assert!(Predicate::Custom(Box::new(|d| d[1] == 0x34)).matches(&digest));
+  }
+
+  #[test]
+  fn miner() {
+    let expected: Vec<(u64, Digest)> = (0..)
+      .zip(Md5Iterator::new("abc"))
+      .filter(|(_, d)| d[0] == 0 && d[1] & 0xf0 == 0)
+      .take(6)
+      .collect();
+    let miner = Miner::<Md5>::new("abc", Predicate::LeadingZeroNibbles(3))
+      .threads(3)
+      .chunk_size(5000);
+    assert_eq!(expected, miner.first(6).unwrap());
+
+    let miner = Miner::<Md5>::new("abc", Predicate::LeadingZeroNibbles(3))
+      .range(expected[1].0..expected[4].0 + 1)
+      .chunk_size(1000);
+    assert_eq!(expected[1..5].to_vec(), miner.first(100).unwrap());
+    assert_eq!(
+      4,
+      miner
+        .chunks()
+        .unwrap()
+        .map(|chunk| chunk.len())
+        .sum::<usize>()
+    );
+
+    let miner = Miner::<Md5>::new("abc", Predicate::LeadingZeroNibbles(3))
+      .range(expected[0].0..expected[1].0 + 1)
+      .chunk_size(0);
+    assert_eq!(expected[0..2].to_vec(), miner.first(2).unwrap());
   }
 }
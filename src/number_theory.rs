@@ -0,0 +1,110 @@
+#![allow(dead_code)]
+
+/// The greatest common divisor `g` of `a` and `b`, along with `x` and `y`
+/// such that `a * x + b * y == g`, as `(g, x, y)`.
+pub fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+  if b == 0 {
+    return if a < 0 { (-a, -1, 0) } else { (a, 1, 0) };
+  }
+  let (g, x, y) = extended_gcd(b, a % b);
+  (g, y, x - (a / b) * y)
+}
+
+pub fn gcd(a: u64, b: u64) -> u64 {
+  extended_gcd(a as i128, b as i128).0 as u64
+}
+
+/// The least common multiple, or `None` if it doesn't fit in a `u64`.
+pub fn lcm(a: u64, b: u64) -> Option<u64> {
+  if a == 0 || b == 0 {
+    return Some(0);
+  }
+  (a / gcd(a, b)).checked_mul(b)
+}
+
+/// The numbers which leave `residue` when divided by `modulus`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Congruence {
+  pub residue: u64,
+  pub modulus: u64,
+}
+impl Congruence {
+  /// `x ≡ residue (mod modulus)`, with any residue, even negative, brought
+  /// into the range `0..modulus`. Panics if the modulus is 0.
+  pub fn new(residue: i128, modulus: u64) -> Self {
+    Self {
+      residue: residue.rem_euclid(modulus as i128) as u64,
+      modulus,
+    }
+  }
+
+  pub fn contains(&self, n: u64) -> bool {
+    n % self.modulus == self.residue
+  }
+
+  /// The numbers satisfying both congruences, whether or not the moduli are
+  /// coprime. `None` if there aren't any, or the combined modulus is too big
+  /// for a `u64`.
+  pub fn combine(&self, other: &Self) -> Option<Self> {
+    let (m1, m2) = (self.modulus as i128, other.modulus as i128);
+    let (r1, r2) = (self.residue as i128, other.residue as i128);
+    let (g, p, _) = extended_gcd(m1, m2);
+    if (r2 - r1) % g != 0 {
+      return None;
+    }
+    let modulus = lcm(self.modulus, other.modulus)?;
+    // m1 * p ≡ g (mod m2), so stepping r1 by m1 * p * (r2 - r1) / g lands on
+    // r2 mod m2 while staying on r1 mod m1.
+    let steps = ((r2 - r1) / g * p).rem_euclid(m2 / g);
+    Some(Self::new(r1 + steps * m1, modulus))
+  }
+}
+
+/// Solves a system of congruences with the generalized Chinese Remainder
+/// Theorem, giving every solution as a single congruence. `None` if the
+/// congruences contradict each other.
+pub fn chinese_remainder(congruences: impl IntoIterator<Item = Congruence>) -> Option<Congruence> {
+  congruences
+    .into_iter()
+    .try_fold(Congruence::new(0, 1), |all, next| all.combine(&next))
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn gcds() {
+    assert_eq!((2, -9, 47), extended_gcd(240, 46));
+    for (a, b) in [(0, 5), (5, 0), (17, 5), (12, 18), (-12, 18), (1 << 40, 6)].iter() {
+      let (g, x, y) = extended_gcd(*a, *b);
+      assert_eq!(g, a * x + b * y, "{} {}", a, b);
+    }
+    assert_eq!(6, gcd(12, 18));
+    assert_eq!(Some(36), lcm(12, 18));
+    assert_eq!(None, lcm(u64::MAX, u64::MAX - 1));
+  }
+
+  #[test]
+  fn congruences() {
+    let solution = chinese_remainder(vec![
+      Congruence::new(2, 3),
+      Congruence::new(3, 5),
+      Congruence::new(2, 7),
+    ]);
+    assert_eq!(Some(Congruence::new(23, 105)), solution);
+    // Not coprime, but consistent.
+    assert_eq!(
+      Some(Congruence::new(20, 24)),
+      chinese_remainder(vec![Congruence::new(2, 6), Congruence::new(4, 8)])
+    );
+    // Odd and even at once.
+    assert_eq!(
+      None,
+      chinese_remainder(vec![Congruence::new(1, 4), Congruence::new(2, 6)])
+    );
+    assert_eq!(Some(Congruence::new(0, 1)), chinese_remainder(vec![]));
+    assert_eq!(Congruence::new(3, 5), Congruence::new(-7, 5));
+    assert!(Congruence::new(3, 5).contains(13));
+  }
+}
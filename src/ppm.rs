@@ -0,0 +1,119 @@
+#![allow(dead_code)]
+
+use std::{
+  fs::File,
+  io::{BufWriter, Write},
+  path::Path,
+};
+
+pub type Rgb = [u8; 3];
+
+/// A simple RGB image which can be saved as a binary PPM, a format which
+/// almost every image tool can read or convert to PNG/GIF.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+  width: usize,
+  height: usize,
+  pixels: Vec<Rgb>,
+}
+impl Image {
+  pub fn new(width: usize, height: usize, background: Rgb) -> Self {
+    Self {
+      width,
+      height,
+      pixels: vec![background; width * height],
+    }
+  }
+
+  pub fn width(&self) -> usize {
+    self.width
+  }
+
+  pub fn height(&self) -> usize {
+    self.height
+  }
+
+  pub fn get(&self, x: usize, y: usize) -> Rgb {
+    self.pixels[y * self.width + x]
+  }
+
+  pub fn set(&mut self, x: usize, y: usize, color: Rgb) {
+    self.pixels[y * self.width + x] = color;
+  }
+
+  /// Blows each pixel up into a `factor` by `factor` square, as tiny images
+  /// are hard to look at.
+  pub fn scaled(&self, factor: usize) -> Self {
+    let mut result = Self::new(self.width * factor, self.height * factor, [0, 0, 0]);
+    for y in 0..result.height {
+      for x in 0..result.width {
+        result.set(x, y, self.get(x / factor, y / factor));
+      }
+    }
+    result
+  }
+
+  pub fn write_ppm(&self, mut output: impl Write) -> std::io::Result<()> {
+    write!(output, "P6\n{} {}\n255\n", self.width, self.height)?;
+    for pixel in self.pixels.iter() {
+      output.write_all(pixel)?;
+    }
+    output.flush()
+  }
+
+  pub fn save_ppm(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+    self.write_ppm(BufWriter::new(File::create(path)?))
+  }
+}
+
+/// Saves animation frames as `{prefix}0000.ppm`, `{prefix}0001.ppm`, etc.
+/// in `dir`, ready for e.g. `ffmpeg -i {prefix}%04d.ppm out.gif`.
+pub fn save_frames(
+  frames: &[Image],
+  dir: impl AsRef<Path>,
+  prefix: &str,
+  scale: usize,
+) -> std::io::Result<()> {
+  std::fs::create_dir_all(&dir)?;
+  for (i, frame) in frames.iter().enumerate() {
+    let path = dir.as_ref().join(format!("{}{:04}.ppm", prefix, i));
+    frame.scaled(scale).save_ppm(path)?;
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn ppm_encoding() {
+    let mut image = Image::new(2, 1, [0, 0, 0]);
+    image.set(1, 0, [255, 128, 1]);
+    let mut output = Vec::new();
+    image.write_ppm(&mut output).unwrap();
+    assert_eq!(b"P6\n2 1\n255\n\x00\x00\x00\xff\x80\x01".to_vec(), output);
+  }
+
+  #[test]
+  fn scaling() {
+    let mut image = Image::new(2, 1, [0, 0, 0]);
+    image.set(1, 0, [1, 2, 3]);
+    let scaled = image.scaled(3);
+    assert_eq!((6, 3), (scaled.width(), scaled.height()));
+    assert_eq!([0, 0, 0], scaled.get(2, 2));
+    assert_eq!([1, 2, 3], scaled.get(3, 0));
+    assert_eq!([1, 2, 3], scaled.get(5, 2));
+  }
+
+  #[test]
+  fn frames() {
+    let dir = crate::test_util::temp_path("ppm_frames");
+    let frames = vec![Image::new(1, 1, [0, 0, 0]), Image::new(1, 1, [9, 9, 9])];
+    save_frames(&frames, &dir, "frame_", 2).unwrap();
+    let second = std::fs::read(dir.join("frame_0001.ppm")).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(b"P6\n2 2\n255\n".len() + 12, second.len());
+    assert!(second.ends_with(&[9; 12]));
+  }
+}
@@ -0,0 +1,275 @@
+#![allow(dead_code)]
+
+use crate::md5::HashFunction;
+
+/// Splits input into 64 byte blocks, and pads the end the way both SHA-1
+/// and SHA-256 do, with the length in bits as a big endian number.
+#[derive(Clone)]
+struct BlockBuffer {
+  buffer: [u8; 64],
+  buffered: usize,
+  length: u64,
+}
+impl BlockBuffer {
+  fn new() -> Self {
+    Self {
+      buffer: [0; 64],
+      buffered: 0,
+      length: 0,
+    }
+  }
+
+  fn update(&mut self, mut data: &[u8], mut compress: impl FnMut(&[u32; 16])) {
+    self.length += data.len() as u64;
+    while !data.is_empty() {
+      let take = (64 - self.buffered).min(data.len());
+      self.buffer[self.buffered..self.buffered + take].copy_from_slice(&data[..take]);
+      self.buffered += take;
+      data = &data[take..];
+      if self.buffered == 64 {
+        compress(&block_words(&self.buffer));
+        self.buffered = 0;
+      }
+    }
+  }
+
+  fn finalize(mut self, mut compress: impl FnMut(&[u32; 16])) {
+    let bit_length = self.length.wrapping_mul(8);
+    self.update(&[0x80], &mut compress);
+    while self.buffered != 56 {
+      self.update(&[0], &mut compress);
+    }
+    self.update(&bit_length.to_be_bytes(), &mut compress);
+  }
+}
+
+fn block_words(block: &[u8; 64]) -> [u32; 16] {
+  std::array::from_fn(|i| {
+    u32::from_be_bytes([
+      block[4 * i],
+      block[4 * i + 1],
+      block[4 * i + 2],
+      block[4 * i + 3],
+    ])
+  })
+}
+
+fn state_digest<const WORDS: usize, const BYTES: usize>(state: &[u32; WORDS]) -> [u8; BYTES] {
+  let mut digest = [0; BYTES];
+  for (bytes, word) in digest.chunks_mut(4).zip(state.iter()) {
+    bytes.copy_from_slice(&word.to_be_bytes());
+  }
+  digest
+}
+
+#[derive(Clone)]
+pub struct Sha1 {
+  state: [u32; 5],
+  blocks: BlockBuffer,
+}
+impl Sha1 {
+  pub fn new() -> Self {
+    Self {
+      state: [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0],
+      blocks: BlockBuffer::new(),
+    }
+  }
+
+  fn compress(state: &mut [u32; 5], block: &[u32; 16]) {
+    let mut w = [0; 80];
+    w[..16].copy_from_slice(block);
+    for i in 16..80 {
+      w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+    }
+    let [mut a, mut b, mut c, mut d, mut e] = *state;
+    for (i, word) in w.iter().enumerate() {
+      let (f, k) = match i / 20 {
+        0 => ((b & c) | (!b & d), 0x5a827999),
+        1 => (b ^ c ^ d, 0x6ed9eba1),
+        2 => ((b & c) | (b & d) | (c & d), 0x8f1bbcdc),
+        _ => (b ^ c ^ d, 0xca62c1d6),
+      };
+      let temp = a
+        .rotate_left(5)
+        .wrapping_add(f)
+        .wrapping_add(e)
+        .wrapping_add(k)
+        .wrapping_add(*word);
+      e = d;
+      d = c;
+      c = b.rotate_left(30);
+      b = a;
+      a = temp;
+    }
+    for (s, v) in state.iter_mut().zip([a, b, c, d, e].iter()) {
+      *s = s.wrapping_add(*v);
+    }
+  }
+}
+impl Default for Sha1 {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+impl HashFunction for Sha1 {
+  type Digest = [u8; 20];
+
+  fn update(&mut self, data: &[u8]) {
+    let state = &mut self.state;
+    self
+      .blocks
+      .update(data, |block| Self::compress(state, block));
+  }
+
+  fn finalize(mut self) -> [u8; 20] {
+    let state = &mut self.state;
+    self.blocks.finalize(|block| Self::compress(state, block));
+    state_digest(&self.state)
+  }
+}
+
+// The first 32 bits of the fractional parts of the cube roots of the first
+// 64 primes, from FIPS 180-4.
+const SHA256_K: [u32; 64] = [
+  0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+  0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+  0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+  0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+  0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+  0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+  0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+  0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+#[derive(Clone)]
+pub struct Sha256 {
+  state: [u32; 8],
+  blocks: BlockBuffer,
+}
+impl Sha256 {
+  pub fn new() -> Self {
+    Self {
+      state: [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+        0x5be0cd19,
+      ],
+      blocks: BlockBuffer::new(),
+    }
+  }
+
+  fn compress(state: &mut [u32; 8], block: &[u32; 16]) {
+    let mut w = [0; 64];
+    w[..16].copy_from_slice(block);
+    for i in 16..64 {
+      let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+      let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+      w[i] = w[i - 16]
+        .wrapping_add(s0)
+        .wrapping_add(w[i - 7])
+        .wrapping_add(s1);
+    }
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+    for (word, k) in w.iter().zip(SHA256_K.iter()) {
+      let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+      let choice = (e & f) ^ (!e & g);
+      let temp1 = h
+        .wrapping_add(s1)
+        .wrapping_add(choice)
+        .wrapping_add(*k)
+        .wrapping_add(*word);
+      let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+      let majority = (a & b) ^ (a & c) ^ (b & c);
+      let temp2 = s0.wrapping_add(majority);
+      h = g;
+      g = f;
+      f = e;
+      e = d.wrapping_add(temp1);
+      d = c;
+      c = b;
+      b = a;
+      a = temp1.wrapping_add(temp2);
+    }
+    for (s, v) in state.iter_mut().zip([a, b, c, d, e, f, g, h].iter()) {
+      *s = s.wrapping_add(*v);
+    }
+  }
+}
+impl Default for Sha256 {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+impl HashFunction for Sha256 {
+  type Digest = [u8; 32];
+
+  fn update(&mut self, data: &[u8]) {
+    let state = &mut self.state;
+    self
+      .blocks
+      .update(data, |block| Self::compress(state, block));
+  }
+
+  fn finalize(mut self) -> [u8; 32] {
+    let state = &mut self.state;
+    self.blocks.finalize(|block| Self::compress(state, block));
+    state_digest(&self.state)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::md5::{HashIterator, Nibbles};
+
+  fn hex<H: HashFunction>(data: &str) -> String {
+    Nibbles::new(&H::compute(data.as_bytes())).to_string()
+  }
+
+  #[test]
+  fn sha1() {
+    assert_eq!("da39a3ee5e6b4b0d3255bfef95601890afd80709", hex::<Sha1>(""));
+    assert_eq!(
+      "a9993e364706816aba3e25717850c26c9cd0d89d",
+      hex::<Sha1>("abc")
+    );
+    assert_eq!(
+      "84983e441c3bd26ebaae4aa1f95129e5e54670f1",
+      hex::<Sha1>("abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq")
+    );
+  }
+
+  #[test]
+  fn sha256() {
+    assert_eq!(
+      "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+      hex::<Sha256>("")
+    );
+    assert_eq!(
+      "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+      hex::<Sha256>("abc")
+    );
+    assert_eq!(
+      "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+      hex::<Sha256>("abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq")
+    );
+  }
+
+  #[test]
+  fn incremental() {
+    let message = "x".repeat(150);
+    let mut hasher = Sha256::new();
+    for piece in message.as_bytes().chunks(7) {
+      hasher.update(piece);
+    }
+    assert_eq!(Sha256::compute(message.as_bytes()), hasher.finalize());
+  }
+
+  #[test]
+  fn iterating() {
+    let digests: Vec<_> = HashIterator::<Sha1>::starting_at("abc", 8)
+      .take(12)
+      .collect();
+    assert_eq!(Sha1::compute(b"abc8"), digests[0]);
+    assert_eq!(Sha1::compute(b"abc19"), digests[11]);
+  }
+}
@@ -0,0 +1,7 @@
+use std::path::PathBuf;
+
+/// A path in the temp directory for a test to write `name` to, unique to
+/// this process so that concurrent test runs don't clobber each other.
+pub fn temp_path(name: &str) -> PathBuf {
+  std::env::temp_dir().join(format!("advent_2016_{}_{}", std::process::id(), name))
+}
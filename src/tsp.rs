@@ -0,0 +1,336 @@
+#![allow(dead_code)]
+
+use std::collections::{BTreeMap, VecDeque};
+
+use crate::grid::{Grid, Point};
+
+/// Shortest path lengths between every pair of a set of points, `None` where
+/// one can't be reached from the other.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Distances {
+  matrix: Vec<Vec<Option<u64>>>,
+}
+impl Distances {
+  /// Finds the distances with a breadth first search from each point, moving
+  /// between locations given by `neighbors`. Only paths of at most `within`
+  /// steps are found, so that searches of unbounded mazes still end when a
+  /// point is walled off.
+  pub fn new<I>(points: &[Point], within: u64, neighbors: impl Fn(Point) -> I) -> Self
+  where
+    I: IntoIterator<Item = Point>,
+  {
+    // The same point can be listed more than once, and each copy needs its
+    // own column filled in.
+    let mut targets: BTreeMap<Point, Vec<usize>> = BTreeMap::new();
+    for (i, point) in points.iter().enumerate() {
+      targets.entry(*point).or_default().push(i);
+    }
+    let matrix = points
+      .iter()
+      .map(|from| {
+        let mut row = vec![None; points.len()];
+        let mut found = 0;
+        let mut queue = VecDeque::new();
+        let mut visited = std::collections::BTreeSet::new();
+        queue.push_back((0, *from));
+        visited.insert(*from);
+        while let Some((steps, point)) = queue.pop_front() {
+          if let Some(indexes) = targets.get(&point) {
+            for idx in indexes {
+              row[*idx] = Some(steps);
+            }
+            found += 1;
+            if found == targets.len() {
+              break;
+            }
+          }
+          if steps >= within {
+            continue;
+          }
+          for neighbor in neighbors(point) {
+            if visited.insert(neighbor) {
+              queue.push_back((steps + 1, neighbor));
+            }
+          }
+        }
+        row
+      })
+      .collect();
+    Self { matrix }
+  }
+
+  /// Distances given directly, where `matrix[from][to]` is the distance
+  /// from one point to another. `None` unless the matrix is square.
+  pub fn from_matrix(matrix: Vec<Vec<Option<u64>>>) -> Option<Self> {
+    if matrix.iter().any(|row| row.len() != matrix.len()) {
+      return None;
+    }
+    Some(Self { matrix })
+  }
+
+  pub fn len(&self) -> usize {
+    self.matrix.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.matrix.is_empty()
+  }
+
+  pub fn get(&self, from: usize, to: usize) -> Option<u64> {
+    self.matrix[from][to]
+  }
+}
+
+// The Held-Karp tables take about 100 MB at this many points, and double in
+// size with each one added.
+pub const MAX_TOUR_POINTS: usize = 20;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TourKind {
+  // Stop at whichever point is visited last.
+  Open,
+  // Go back to the start after visiting every point.
+  Return,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tour {
+  pub length: u64,
+  // Indexes of the points in visiting order, starting with 0. Return tours
+  // end with 0 again.
+  pub order: Vec<usize>,
+}
+
+/// The shortest tour starting at point 0 and visiting every point, found
+/// exactly with the Held-Karp dynamic program. Takes O(2^n * n^2) time and
+/// O(2^n * n) memory, so is only for a small number of points. `None` if some
+/// point can't be reached, or if there are more than `MAX_TOUR_POINTS`.
+pub fn shortest_tour(distances: &Distances, kind: TourKind) -> Option<Tour> {
+  let n = distances.len();
+  if n == 0 || n > MAX_TOUR_POINTS {
+    return None;
+  }
+  // The tables are indexed by `index(visited, last)`, for the shortest path
+  // from 0 which visits exactly the points in the `visited` bitmask and ends
+  // at `last`. Every path starts at 0, so bit 0 of `visited` is always set and
+  // is left out of the index.
+  const UNREACHED: u64 = u64::MAX;
+  let index = |visited: usize, last: usize| (visited >> 1) * n + last;
+  let subsets = 1 << n;
+  let mut best = vec![UNREACHED; (subsets >> 1) * n];
+  let mut previous = vec![0u8; (subsets >> 1) * n];
+  best[index(1, 0)] = 0;
+  for visited in (1..subsets).step_by(2) {
+    for last in 0..n {
+      let so_far = best[index(visited, last)];
+      if so_far == UNREACHED {
+        continue;
+      }
+      for next in 0..n {
+        if visited & (1 << next) != 0 {
+          continue;
+        }
+        let step = match distances.get(last, next) {
+          Some(step) => step,
+          None => continue,
+        };
+        let with_next = index(visited | (1 << next), next);
+        let length = match so_far.checked_add(step) {
+          Some(length) => length,
+          None => continue,
+        };
+        if length < best[with_next] {
+          best[with_next] = length;
+          previous[with_next] = last as u8;
+        }
+      }
+    }
+  }
+
+  let all = subsets - 1;
+  let (length, mut last) = (0..n)
+    .filter_map(|last| {
+      let length = best[index(all, last)];
+      if length == UNREACHED {
+        return None;
+      }
+      match kind {
+        TourKind::Open => Some((length, last)),
+        TourKind::Return if n == 1 => Some((length, last)),
+        TourKind::Return => Some((length.checked_add(distances.get(last, 0)?)?, last)),
+      }
+    })
+    .min()?;
+  let mut order = Vec::with_capacity(n + 1);
+  if kind == TourKind::Return && n > 1 {
+    order.push(0);
+  }
+  let mut visited = all;
+  while visited != 1 {
+    order.push(last);
+    let before = previous[index(visited, last)] as usize;
+    visited &= !(1 << last);
+    last = before;
+  }
+  order.push(0);
+  order.reverse();
+  Some(Tour { length, order })
+}
+
+/// The numbered locations in a map like `#0.1#`, with the point marked `0`
+/// first and the rest in numeric order.
+pub fn numbered_points(map: &Grid<char>) -> Vec<Point> {
+  let mut points: Vec<(u32, Point)> = map
+    .points()
+    .filter_map(|p| map[p].to_digit(10).map(|digit| (digit, p)))
+    .collect();
+  points.sort();
+  points.into_iter().map(|(_, p)| p).collect()
+}
+
+/// The shortest tour of the numbered locations in a map where `#` is a wall,
+/// starting from `0`.
+pub fn shortest_tour_of_map(map: &Grid<char>, kind: TourKind) -> Option<Tour> {
+  let distances = Distances::new(&numbered_points(map), u64::MAX, |point| {
+    map
+      .neighbors(point)
+      .filter(|p| map[*p] != '#')
+      .collect::<Vec<_>>()
+  });
+  shortest_tour(&distances, kind)
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  static EXAMPLE: &str = "\
+    ###########\n\
+    #0.1.....2#\n\
+    #.#######.#\n\
+    #4.......3#\n\
+    ###########\n\
+  ";
+
+  #[test]
+  fn distances() {
+    let map: Grid<char> = EXAMPLE.parse().unwrap();
+    let points = numbered_points(&map);
+    assert_eq!(Point::new(1, 1), points[0]);
+    assert_eq!(Point::new(1, 3), points[4]);
+    let distances = Distances::new(&points, u64::MAX, |point| {
+      map
+        .neighbors(point)
+        .filter(|p| map[*p] != '#')
+        .collect::<Vec<_>>()
+    });
+    assert_eq!(Some(2), distances.get(0, 4));
+    assert_eq!(Some(2), distances.get(0, 1));
+    assert_eq!(Some(10), distances.get(2, 4));
+    assert_eq!(Some(0), distances.get(3, 3));
+  }
+
+  #[test]
+  fn example() {
+    let map: Grid<char> = EXAMPLE.parse().unwrap();
+    assert_eq!(
+      Some(Tour {
+        length: 14,
+        order: vec![0, 4, 1, 2, 3]
+      }),
+      shortest_tour_of_map(&map, TourKind::Open)
+    );
+    assert_eq!(
+      Some(20),
+      shortest_tour_of_map(&map, TourKind::Return).map(|t| t.length)
+    );
+    let tour = shortest_tour_of_map(&map, TourKind::Return).unwrap();
+    assert_eq!(
+      (Some(&0), Some(&0), 6),
+      (tour.order.first(), tour.order.last(), tour.order.len())
+    );
+  }
+
+  #[test]
+  fn duplicate_points() {
+    let map: Grid<char> = EXAMPLE.parse().unwrap();
+    let mut points = numbered_points(&map);
+    points.insert(2, points[0]);
+    let distances = Distances::new(&points, u64::MAX, |point| {
+      map
+        .neighbors(point)
+        .filter(|p| map[*p] != '#')
+        .collect::<Vec<_>>()
+    });
+    assert_eq!(Some(0), distances.get(0, 2));
+    assert_eq!(Some(2), distances.get(2, 1));
+    assert_eq!(Some(8), distances.get(3, 2));
+    assert_eq!(
+      Some(14),
+      shortest_tour(&distances, TourKind::Open).map(|t| t.length)
+    );
+  }
+
+  #[test]
+  fn too_many_points() {
+    let n = MAX_TOUR_POINTS + 1;
+    assert_eq!(
+      None,
+      shortest_tour(
+        &Distances::from_matrix(vec![vec![Some(1); n]; n]).unwrap(),
+        TourKind::Open,
+      )
+    );
+  }
+
+  #[test]
+  fn unreachable() {
+    let distances = Distances::from_matrix(vec![vec![Some(0), None], vec![None, Some(0)]]).unwrap();
+    assert_eq!(None, shortest_tour(&distances, TourKind::Open));
+    let distances =
+      Distances::from_matrix(vec![vec![Some(0), Some(3)], vec![None, Some(0)]]).unwrap();
+    assert_eq!(
+      Some(Tour {
+        length: 3,
+        order: vec![0, 1]
+      }),
+      shortest_tour(&distances, TourKind::Open)
+    );
+    assert_eq!(None, shortest_tour(&distances, TourKind::Return));
+    let single = Distances::from_matrix(vec![vec![Some(0)]]).unwrap();
+    assert_eq!(
+      Some(Tour {
+        length: 0,
+        order: vec![0]
+      }),
+      shortest_tour(&single, TourKind::Return)
+    );
+  }
+
+  #[test]
+  fn huge_distances() {
+    let huge = Some(u64::MAX - 1);
+    let distances = Distances::from_matrix(vec![
+      vec![Some(0), huge, Some(1)],
+      vec![huge, Some(0), Some(2)],
+      vec![Some(1), Some(2), Some(0)],
+    ])
+    .unwrap();
+    assert_eq!(
+      Some(Tour {
+        length: 3,
+        order: vec![0, 2, 1]
+      }),
+      shortest_tour(&distances, TourKind::Open)
+    );
+    // Going back to the start would take too long to count.
+    assert_eq!(None, shortest_tour(&distances, TourKind::Return));
+  }
+
+  #[test]
+  fn matrix_shapes() {
+    assert!(Distances::from_matrix(vec![]).is_some());
+    assert!(Distances::from_matrix(vec![vec![Some(0), None]]).is_none());
+    assert!(Distances::from_matrix(vec![vec![Some(0), None], vec![None]]).is_none());
+  }
+}